@@ -0,0 +1,90 @@
+// 저장 전 압축 방식별 크기·시간 비교
+// cargo run --release --example compression_bench -- [할 일 개수]
+use serde_json::json;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+const ROUNDS: u32 = 5;
+
+type Codec = fn(&[u8]) -> Vec<u8>;
+
+// 실제 AppData와 비슷한 모양의 JSON (할 일 텍스트·태그·하위 할 일, 스탑워치 랩 기록)
+fn sample_data(tasks: usize) -> Vec<u8> {
+    let tasks: Vec<_> = (0..tasks)
+        .map(|i| {
+            let priority = ["none", "low", "medium", "high"][i % 4];
+            json!({
+                "id": i + 1,
+                "text": format!("주간 보고서 초안 작성 #{i} — 지난주 회의 내용 정리하고 팀원 피드백 반영"),
+                "completed": i % 3 == 0,
+                "created_at": 1_700_000_000 + i as i64 * 3600,
+                "completed_at": if i % 3 == 0 { json!(1_700_100_000 + i as i64 * 3600) } else { json!(null) },
+                "due_at": null,
+                "reminder_offsets": [600, 3600],
+                "reminders_fired": [],
+                "priority": priority,
+                "tags": [format!("project-{}", i % 7), "work"],
+                "subtasks": (0..i % 4).map(|j| json!({ "id": j + 1, "text": format!("하위 작업 {j}"), "completed": j % 2 == 0 })).collect::<Vec<_>>(),
+                "progress": null,
+                "recurrence": null,
+                "list_id": 1 + i % 3,
+            })
+        })
+        .collect();
+    let laps: Vec<u64> = (0..tasks.len() as u64 * 4).map(|i| 60_000 + i * 1_337 % 90_000).collect();
+    serde_json::to_vec(&json!({
+        "v": 4,
+        "tasks": tasks,
+        "stopwatch": { "elapsed_ms": laps.iter().sum::<u64>(), "lap_totals_ms": laps },
+        "next_id": tasks.len() + 1,
+        "compression": "zstd",
+    }))
+    .unwrap()
+}
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn inflate(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    flate2::read::DeflateDecoder::new(data).read_to_end(&mut out).unwrap();
+    out
+}
+
+// 여러 번 실행한 평균 시간
+fn time<T>(f: impl Fn() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    for _ in 1..ROUNDS {
+        f();
+    }
+    let out = f();
+    (out, start.elapsed() / ROUNDS)
+}
+
+fn main() {
+    let tasks = std::env::args().nth(1).and_then(|n| n.parse().ok()).unwrap_or(20_000);
+    let data = sample_data(tasks);
+    println!("{tasks} tasks, {} bytes of JSON", data.len());
+    println!("{:<8} {:>12} {:>8} {:>12} {:>12}", "method", "bytes", "ratio", "compress", "decompress");
+
+    let methods: [(&str, Codec, Codec); 3] = [
+        ("none", |d| d.to_vec(), |d| d.to_vec()),
+        ("deflate", deflate, inflate),
+        ("zstd", |d| zstd::encode_all(d, 3).unwrap(), |d| zstd::decode_all(d).unwrap()),
+    ];
+    for (name, compress, decompress) in methods {
+        let (packed, compress_time) = time(|| compress(&data));
+        let (unpacked, decompress_time) = time(|| decompress(&packed));
+        assert_eq!(unpacked, data);
+        println!(
+            "{name:<8} {:>12} {:>7.1}% {:>12.2?} {:>12.2?}",
+            packed.len(),
+            packed.len() as f64 / data.len() as f64 * 100.0,
+            compress_time,
+            decompress_time,
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use serde::Serialize;
+use std::fmt;
+
+/// 프론트엔드에 전달되는 안정적인 에러 코드. 문구가 바뀌어도 코드는 바뀌지 않는다.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidInput,       // 잘못된 인자 (빈 텍스트, 잘못된 날짜·검색어 등)
+    NotFound,           // 할 일·목록·스냅샷 등이 없음
+    InvalidState,       // 지금 상태에서는 할 수 없는 작업 (되돌릴 기록 없음, 이미 잠금 모드 등)
+    Conflict,           // 같은 ID의 항목이 이미 있음
+    Locked,             // 잠금 모드에서 unlock 전
+    RecoveryMode,       // 저장된 데이터를 읽지 못해 복구 모드
+    PassphraseRequired, // 비밀번호로 보호된 파일인데 비밀번호가 없음
+    WrongPassphrase,
+    KeyNotFound,        // 데이터 키를 찾을 수 없음
+    WrongKey,           // 다른 키(교체된 키, 다른 기기의 키)로 암호화됨
+    Keyring,            // OS 키체인 접근 실패
+    Tampered,           // 인증 실패 (변조되었거나 키가 맞지 않음)
+    Corrupted,          // 잘렸거나 깨진 파일·JSON
+    UnsupportedVersion, // 알 수 없는(더 새로운) 형식·스키마 버전
+    Io,
+    Crypto,
+}
+
+/// 저장소와 명령이 반환하는 에러. 프론트엔드에는 `{code, message, details}`로 직렬화된다.
+#[derive(Clone, Debug, Serialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>, // 원래 에러 (라이브러리 메시지 등)
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: impl fmt::Display) -> Self {
+        self.details = Some(details.to_string());
+        self
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn invalid_state(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidState, message)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.details {
+            Some(details) => write!(f, "{}: {details}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        // Read/Write 계층(청크 복호화 등)을 거쳐 온 AppError는 코드를 그대로 살린다
+        if let Some(inner) = e.get_ref().and_then(|inner| inner.downcast_ref::<AppError>()) {
+            return inner.clone();
+        }
+        let code = match e.kind() {
+            std::io::ErrorKind::NotFound => ErrorCode::NotFound,
+            // 압축 해제 중 잘린 데이터 등
+            std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof => ErrorCode::Corrupted,
+            _ => ErrorCode::Io,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            return std::io::Error::from(e).into();
+        }
+        Self::new(ErrorCode::Corrupted, e.to_string())
+    }
+}
+
+impl From<keyring::Error> for AppError {
+    fn from(e: keyring::Error) -> Self {
+        let code = match e {
+            keyring::Error::NoEntry => ErrorCode::KeyNotFound,
+            _ => ErrorCode::Keyring,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<base64::DecodeError> for AppError {
+    fn from(e: base64::DecodeError) -> Self {
+        Self::new(ErrorCode::Corrupted, e.to_string())
+    }
+}
+
+// AES-GCM 에러는 내용이 없으므로 (복호화 실패 = 인증 실패) 호출한 쪽에서 원인을 붙인다
+impl From<aes_gcm::Error> for AppError {
+    fn from(e: aes_gcm::Error) -> Self {
+        Self::new(ErrorCode::Tampered, e.to_string())
+    }
+}
+
+impl From<aes_gcm::aes::cipher::InvalidLength> for AppError {
+    fn from(e: aes_gcm::aes::cipher::InvalidLength) -> Self {
+        Self::new(ErrorCode::Crypto, e.to_string())
+    }
+}
+
+impl From<argon2::Error> for AppError {
+    fn from(e: argon2::Error) -> Self {
+        Self::new(ErrorCode::Crypto, e.to_string())
+    }
+}
+
+/// 하위 에러를 코드는 유지한 채 설명을 붙여 AppError로 바꾼다 (원래 메시지는 details로).
+pub trait Context<T> {
+    fn context(self, message: &str) -> AppResult<T>;
+    fn context_as(self, code: ErrorCode, message: &str) -> AppResult<T>;
+}
+
+impl<T, E: Into<AppError>> Context<T> for Result<T, E> {
+    fn context(self, message: &str) -> AppResult<T> {
+        self.map_err(|e| {
+            let e = e.into();
+            AppError::new(e.code, message).with_details(e)
+        })
+    }
+
+    fn context_as(self, code: ErrorCode, message: &str) -> AppResult<T> {
+        self.map_err(|e| AppError::new(code, message).with_details(e.into()))
+    }
+}
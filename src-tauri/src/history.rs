@@ -0,0 +1,316 @@
+use crate::error::{AppError, AppResult};
+use crate::{now_secs, AppData, StopwatchState, TaskList, TodoItem, TrashedTask};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const MAX_ENTRIES: usize = 50;
+// 스탑워치는 자주 저장되므로 짧은 간격 안의 변경은 하나의 기록으로 합침
+const STOPWATCH_COALESCE_SECS: i64 = 10;
+
+/// 실행 취소/다시 실행 기록. AppData와 함께 암호화 저장되므로 재시작 후에도 되돌릴 수 있다.
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct History {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry {
+    label: String, // 기록을 남긴 명령 이름 (예: "delete_task")
+    at: i64,
+    op: Op,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Op {
+    // 변경 전후의 할 일과 목록 내 위치 (추가는 before가, 삭제는 after가 비어 있음)
+    Tasks {
+        before: Vec<(usize, TodoItem)>,
+        after: Vec<(usize, TodoItem)>,
+    },
+    // 휴지통으로 옮긴 할 일 (실행 취소하면 휴지통에서 꺼내 원래 위치로)
+    Trash {
+        items: Vec<TrashedTask>,
+    },
+    // 목록 삭제 (목록과 함께 휴지통으로 옮긴 소속 할 일을 되살림)
+    DeleteList {
+        list: TaskList,
+        position: usize,
+        items: Vec<TrashedTask>,
+    },
+    Stopwatch {
+        before: Option<StopwatchState>,
+        after: Option<StopwatchState>,
+    },
+    // 가져오기처럼 전체 데이터가 바뀌는 경우 (스냅샷의 history는 비워서 저장).
+    // 전체 사본 두 개를 들고 있으므로 가장 최근 것 하나만 남김
+    Replace {
+        before: Box<AppData>,
+        after: Box<AppData>,
+    },
+}
+
+#[derive(Clone, Serialize)]
+pub struct HistoryStatus {
+    can_undo: bool,
+    can_redo: bool,
+    undo_label: Option<String>,
+    redo_label: Option<String>,
+}
+
+impl History {
+    pub fn status(&self) -> HistoryStatus {
+        HistoryStatus {
+            can_undo: !self.undo.is_empty(),
+            can_redo: !self.redo.is_empty(),
+            undo_label: self.undo.last().map(|e| e.label.clone()),
+            redo_label: self.redo.last().map(|e| e.label.clone()),
+        }
+    }
+}
+
+/// history를 비운 데이터 사본 (Replace 스냅샷·내보내기용)
+pub fn without_history(data: &AppData) -> AppData {
+    let mut copy = data.clone();
+    copy.history = History::default();
+    copy
+}
+
+pub fn record(data: &mut AppData, label: &str, op: Op) {
+    let now = now_secs();
+    let history = &mut data.history;
+    history.redo.clear();
+
+    if let Op::Stopwatch { after, .. } = &op {
+        if let Some(Entry {
+            at,
+            op: Op::Stopwatch { after: last_after, .. },
+            ..
+        }) = history.undo.last_mut()
+        {
+            if now - *at <= STOPWATCH_COALESCE_SECS {
+                *last_after = after.clone();
+                *at = now;
+                return;
+            }
+        }
+    }
+
+    if matches!(op, Op::Replace { .. }) {
+        history.undo.retain(|e| !matches!(e.op, Op::Replace { .. }));
+    }
+
+    history.undo.push(Entry {
+        label: label.to_string(),
+        at: now,
+        op,
+    });
+    if history.undo.len() > MAX_ENTRIES {
+        let excess = history.undo.len() - MAX_ENTRIES;
+        history.undo.drain(..excess);
+    }
+}
+
+pub fn undo(data: &mut AppData) -> AppResult<String> {
+    let entry = data.history.undo.pop().ok_or_else(|| AppError::invalid_state("nothing to undo"))?;
+    apply(data, &entry.op, false);
+    let label = entry.label.clone();
+    data.history.redo.push(entry);
+    Ok(label)
+}
+
+pub fn redo(data: &mut AppData) -> AppResult<String> {
+    let entry = data.history.redo.pop().ok_or_else(|| AppError::invalid_state("nothing to redo"))?;
+    apply(data, &entry.op, true);
+    let label = entry.label.clone();
+    data.history.undo.push(entry);
+    Ok(label)
+}
+
+// forward=true면 다시 실행(before → after), false면 실행 취소(after → before)
+fn apply(data: &mut AppData, op: &Op, forward: bool) {
+    match op {
+        Op::Tasks { before, after } => {
+            let (from, to) = if forward { (before, after) } else { (after, before) };
+            // 반대쪽에만 있는 할 일(추가된 항목 등)은 제거
+            data.tasks.retain(|t| {
+                !from.iter().any(|(_, f)| f.id == t.id) || to.iter().any(|(_, n)| n.id == t.id)
+            });
+            let mut to = to.clone();
+            to.sort_by_key(|(pos, _)| *pos);
+            for (pos, item) in to {
+                match from.iter().find(|(_, f)| f.id == item.id) {
+                    // 양쪽에 있는 할 일은 이 기록이 바꾼 필드만 되돌려 이후의 수정(본문·태그 등)은 유지
+                    // (기록 이후 삭제된 할 일은 건너뜀)
+                    Some((_, recorded)) => {
+                        if let Some(current) = data.tasks.iter_mut().find(|t| t.id == item.id) {
+                            *current = merge_task(current, recorded, &item);
+                        }
+                    }
+                    None => {
+                        data.tasks.retain(|t| t.id != item.id);
+                        let pos = pos.min(data.tasks.len());
+                        data.tasks.insert(pos, item);
+                    }
+                }
+            }
+        }
+        Op::Trash { items } => apply_trash(data, items, forward),
+        Op::DeleteList { list, position, items } => {
+            data.lists.retain(|l| l.id != list.id);
+            if !forward {
+                let pos = (*position).min(data.lists.len());
+                data.lists.insert(pos, list.clone());
+            }
+            apply_trash(data, items, forward);
+        }
+        Op::Stopwatch { before, after } => {
+            data.stopwatch = if forward { after.clone() } else { before.clone() };
+        }
+        Op::Replace { before, after } => {
+            let history = std::mem::take(&mut data.history);
+            // 그사이 발급(보관)된 ID를 다시 쓰지 않도록 next_id는 되돌리지 않음
+            let next_id = data.next_id;
+            *data = if forward { (**after).clone() } else { (**before).clone() };
+            data.history = history;
+            data.next_id = data.next_id.max(next_id);
+        }
+    }
+}
+
+fn apply_trash(data: &mut AppData, items: &[TrashedTask], forward: bool) {
+    let ids: Vec<u64> = items.iter().map(|t| t.task.id).collect();
+    data.tasks.retain(|t| !ids.contains(&t.id));
+    data.trash.retain(|t| !ids.contains(&t.task.id));
+    if forward {
+        data.trash.extend(items.iter().cloned());
+    } else {
+        let mut items = items.to_vec();
+        items.sort_by_key(|t| t.position);
+        for trashed in items {
+            let pos = trashed.position.min(data.tasks.len());
+            data.tasks.insert(pos, trashed.task);
+        }
+    }
+}
+
+// current에 from → to 사이에 바뀐 필드만 적용
+fn merge_task(current: &TodoItem, from: &TodoItem, to: &TodoItem) -> TodoItem {
+    let (Ok(mut merged), Ok(from_value), Ok(to_value)) = (
+        serde_json::to_value(current),
+        serde_json::to_value(from),
+        serde_json::to_value(to),
+    ) else {
+        return to.clone();
+    };
+    merge_value(&mut merged, &from_value, &to_value);
+    let mut item: TodoItem = serde_json::from_value(merged).unwrap_or_else(|_| to.clone());
+    item.refresh_progress();
+    item
+}
+
+// 객체는 필드별, id가 같은 원소들로 이뤄진 배열(하위 할 일)은 원소별로 합치고 나머지는 통째로 교체
+fn merge_value(current: &mut Value, from: &Value, to: &Value) {
+    if from == to {
+        return;
+    }
+    match (current, from, to) {
+        (Value::Object(current), Value::Object(from), Value::Object(to)) => {
+            for (key, to) in to {
+                let from = from.get(key).unwrap_or(&Value::Null);
+                match current.get_mut(key) {
+                    Some(current) => merge_value(current, from, to),
+                    None => {
+                        current.insert(key.clone(), to.clone());
+                    }
+                }
+            }
+        }
+        (Value::Array(current), Value::Array(from), Value::Array(to))
+            if element_ids(from).is_some() && element_ids(from) == element_ids(to) =>
+        {
+            for (from, to) in from.iter().zip(to) {
+                if let Some(current) = current.iter_mut().find(|c| c.get("id") == to.get("id")) {
+                    merge_value(current, from, to);
+                }
+            }
+        }
+        (current, _, to) => *current = to.clone(),
+    }
+}
+
+fn element_ids(values: &[Value]) -> Option<Vec<&Value>> {
+    values.iter().map(|v| v.get("id")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Subtask;
+
+    #[test]
+    fn undo_toggle_keeps_later_edits() {
+        let mut data = AppData::default();
+        let mut task = TodoItem::for_test(1, "original", 0);
+        task.subtasks.push(Subtask {
+            id: 10,
+            text: "a".to_string(),
+            completed: false,
+            created_at: 0,
+            completed_at: None,
+        });
+        data.tasks.push(task);
+
+        // toggle_task: 완료하면서 하위 할 일도 완료
+        let before = data.tasks[0].clone();
+        data.tasks[0].completed = true;
+        data.tasks[0].completed_at = Some(100);
+        data.tasks[0].subtasks[0].completed = true;
+        data.tasks[0].refresh_progress();
+        let after = data.tasks[0].clone();
+        let op = Op::Tasks {
+            before: vec![(0, before)],
+            after: vec![(0, after)],
+        };
+        record(&mut data, "toggle_task", op);
+
+        // 기록 없이 이후에 수정 (update_task, 태그, 하위 할 일 본문)
+        data.tasks[0].text = "edited".to_string();
+        data.tasks[0].tags.insert("work".to_string());
+        data.tasks[0].subtasks[0].text = "renamed".to_string();
+
+        undo(&mut data).unwrap();
+        let t = &data.tasks[0];
+        assert!(!t.completed);
+        assert_eq!(t.completed_at, None);
+        assert!(!t.subtasks[0].completed);
+        assert_eq!(t.progress, Some(0.0));
+        assert_eq!(t.text, "edited");
+        assert!(t.tags.contains("work"));
+        assert_eq!(t.subtasks[0].text, "renamed");
+
+        redo(&mut data).unwrap();
+        let t = &data.tasks[0];
+        assert!(t.completed);
+        assert!(t.subtasks[0].completed);
+        assert_eq!(t.text, "edited");
+    }
+
+    #[test]
+    fn only_latest_replace_is_kept() {
+        let mut data = AppData::default();
+        for label in ["import_data", "restore_snapshot"] {
+            let op = Op::Replace {
+                before: Box::new(AppData::default()),
+                after: Box::new(AppData::default()),
+            };
+            record(&mut data, label, op);
+        }
+        record(&mut data, "delete_task", Op::Trash { items: Vec::new() });
+        let replaces = data.history.undo.iter().filter(|e| matches!(e.op, Op::Replace { .. })).count();
+        assert_eq!(replaces, 1);
+        assert_eq!(data.history.undo.len(), 2);
+        assert_eq!(data.history.undo[0].label, "restore_snapshot");
+    }
+}
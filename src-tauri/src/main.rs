@@ -2,12 +2,22 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
+mod error;
+mod history;
+mod migrations;
+mod persistence;
+mod recovery;
+mod scheduler;
+mod search;
 mod storage;
+mod stream;
 
-use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use serde::{Deserialize, Deserializer, Serialize};
+use error::{AppError, AppResult, Context, ErrorCode};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Mutex, MutexGuard};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::{Manager, WindowEvent};
+use tauri::{Manager, RunEvent, WindowEvent};
 
 #[derive(Clone, Serialize, Deserialize)]
 struct TodoItem {
@@ -16,6 +26,325 @@ struct TodoItem {
     completed: bool,
     created_at: i64,
     completed_at: Option<i64>, // 완료 시각 (통계용)
+    #[serde(default)]
+    due_at: Option<i64>, // 마감 시각 (초)
+    #[serde(default)]
+    reminder_offsets: Vec<i64>, // 마감 몇 초 전에 알릴지 (비어 있으면 마감 시각에 한 번)
+    #[serde(default)]
+    reminders_fired: Vec<i64>, // 이미 알린 offset (재시작 후 중복 알림 방지)
+    #[serde(default)]
+    priority: Priority,
+    #[serde(default)]
+    tags: BTreeSet<String>,
+    #[serde(default)]
+    subtasks: Vec<Subtask>,
+    #[serde(default)]
+    progress: Option<f64>, // 완료된 하위 할 일 비율 (하위 할 일이 없으면 None)
+    #[serde(default)]
+    recurrence: Option<Recurrence>,
+    #[serde(default)]
+    list_id: u64, // 소속 목록 (구버전 데이터는 0 → 로드 시 기본 목록으로 이전)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct TaskList {
+    id: u64,
+    name: String,
+    archived: bool,
+    created_at: i64,
+}
+
+const DEFAULT_LIST_NAME: &str = "기본";
+const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+const DEFAULT_ARCHIVE_AFTER_DAYS: u32 = 30;
+// 마감 시각 상한 (9999-12-31 23:59:59 UTC). 날짜 계산이 chrono 범위를 넘지 않도록 제한
+const MAX_TIMESTAMP: i64 = 253_402_300_799;
+const MAX_RECURRENCE_INTERVAL: u32 = 1000;
+
+fn default_trash_retention_days() -> u32 {
+    DEFAULT_TRASH_RETENTION_DAYS
+}
+
+fn default_archive_after_days() -> u32 {
+    DEFAULT_ARCHIVE_AFTER_DAYS
+}
+
+// 휴지통의 할 일 (복원 시 원래 위치로 되돌리기 위해 삭제 당시 인덱스를 함께 보관)
+#[derive(Clone, Serialize, Deserialize)]
+struct TrashedTask {
+    task: TodoItem,
+    deleted_at: i64,
+    position: usize,
+}
+
+fn normalize_list_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::invalid("list name must not be empty"));
+    }
+    Ok(name.to_string())
+}
+
+// 반복 규칙 (시각은 통계와 마찬가지로 UTC 기준으로 계산)
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "freq", rename_all = "snake_case")]
+enum Recurrence {
+    Daily { interval: u32 },  // N일마다
+    Weekdays,                 // 평일(월~금)마다
+    Weekly { interval: u32 }, // N주마다
+    Monthly { day: u32 },     // 매월 X일 (그 달에 없는 날이면 말일)
+}
+
+impl Recurrence {
+    fn validate(&self) -> AppResult<()> {
+        match self {
+            Recurrence::Daily { interval } | Recurrence::Weekly { interval }
+                if !(1..=MAX_RECURRENCE_INTERVAL).contains(interval) =>
+            {
+                Err(AppError::invalid(format!(
+                    "recurrence interval must be between 1 and {MAX_RECURRENCE_INTERVAL}"
+                )))
+            }
+            Recurrence::Monthly { day } if !(1..=31).contains(day) => {
+                Err(AppError::invalid("recurrence day must be between 1 and 31"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // base 다음 회차의 시각 (시:분:초는 base와 동일). 저장된 마감 시각이 범위를 벗어나면 에러
+    fn next_after(&self, base: i64) -> AppResult<i64> {
+        use chrono::{Datelike, TimeZone, Weekday};
+        let out_of_range = || AppError::invalid(format!("due date out of range: {base}"));
+        if !(0..=MAX_TIMESTAMP).contains(&base) {
+            return Err(out_of_range());
+        }
+        let dt = chrono::Utc.timestamp_opt(base, 0).single().ok_or_else(out_of_range)?;
+        let next = match self {
+            Recurrence::Daily { interval } => base + *interval as i64 * 86400,
+            Recurrence::Weekly { interval } => base + *interval as i64 * 7 * 86400,
+            Recurrence::Weekdays => {
+                let skip = match dt.weekday() {
+                    Weekday::Fri => 3,
+                    Weekday::Sat => 2,
+                    _ => 1,
+                };
+                base + skip * 86400
+            }
+            Recurrence::Monthly { day } => {
+                // 이번 달의 지정일이 아직 남아 있으면 이번 달, 아니면 다음 달
+                let this_month = (*day).min(days_in_month(dt.year(), dt.month()));
+                let (year, month) = if this_month > dt.day() {
+                    (dt.year(), dt.month())
+                } else if dt.month() == 12 {
+                    (dt.year() + 1, 1)
+                } else {
+                    (dt.year(), dt.month() + 1)
+                };
+                let last_day = days_in_month(year, month);
+                chrono::NaiveDate::from_ymd_opt(year, month, (*day).min(last_day))
+                    .map(|d| d.and_time(dt.time()).and_utc().timestamp())
+                    .unwrap_or(base + 30 * 86400)
+            }
+        };
+        // 검증 전에 저장된 간격이라도 다음 계산이 범위를 넘지 않게 함
+        if next > MAX_TIMESTAMP {
+            return Err(AppError::invalid("next occurrence is out of range"));
+        }
+        Ok(next)
+    }
+
+    // 완료 시점 이후의 첫 회차 (밀린 회차는 건너뜀)
+    fn next_occurrence(&self, due: i64, now: i64) -> AppResult<i64> {
+        let mut next = self.next_after(due)?;
+        while next <= now {
+            next = self.next_after(next)?;
+        }
+        Ok(next)
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    chrono::NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| chrono::Datelike::day(&d))
+        .unwrap_or(28)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Subtask {
+    id: u64,
+    text: String,
+    completed: bool,
+    created_at: i64,
+    completed_at: Option<i64>,
+}
+
+// 테스트에서 쓰는 최소 할 일 (미완료, 마감·하위 할 일 없음)
+#[cfg(test)]
+impl TodoItem {
+    fn for_test(id: u64, text: &str, created_at: i64) -> Self {
+        TodoItem {
+            id,
+            text: text.to_string(),
+            completed: false,
+            created_at,
+            completed_at: None,
+            due_at: None,
+            reminder_offsets: Vec::new(),
+            reminders_fired: Vec::new(),
+            priority: Priority::default(),
+            tags: BTreeSet::new(),
+            subtasks: Vec::new(),
+            progress: None,
+            recurrence: None,
+            list_id: 0,
+        }
+    }
+}
+
+impl TodoItem {
+    fn refresh_progress(&mut self) {
+        self.progress = if self.subtasks.is_empty() {
+            None
+        } else {
+            let done = self.subtasks.iter().filter(|s| s.completed).count();
+            Some(done as f64 / self.subtasks.len() as f64)
+        };
+    }
+
+    fn subtask_mut(&mut self, subtask_id: u64) -> AppResult<&mut Subtask> {
+        self.subtasks
+            .iter_mut()
+            .find(|s| s.id == subtask_id)
+            .ok_or_else(|| AppError::not_found(format!("subtask not found: {subtask_id}")))
+    }
+}
+
+// 앞뒤 공백과 '#' 접두사를 제거한 태그 (빈 태그는 None)
+fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').trim();
+    if tag.is_empty() {
+        None
+    } else {
+        Some(tag.to_string())
+    }
+}
+
+fn normalize_tags(tags: &[String]) -> BTreeSet<String> {
+    tags.iter().filter_map(|t| normalize_tag(t)).collect()
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct TagCount {
+    name: String,
+    count: u32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+}
+
+// get_tasks 정렬 기준 (기본값은 사용자가 지정한 순서)
+#[derive(Clone, Copy, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum TaskSort {
+    #[default]
+    Manual,
+    Priority,
+    DueDate,
+    Created,
+}
+
+fn sort_tasks(tasks: &mut [TodoItem], sort: TaskSort) {
+    // 안정 정렬이므로 같은 값끼리는 수동 순서가 유지됨
+    match sort {
+        TaskSort::Manual => {}
+        TaskSort::Priority => tasks.sort_by_key(|t| std::cmp::Reverse(t.priority)),
+        TaskSort::DueDate => tasks.sort_by_key(|t| (t.due_at.is_none(), t.due_at)),
+        TaskSort::Created => tasks.sort_by_key(|t| std::cmp::Reverse(t.created_at)),
+    }
+}
+
+// 필드가 없으면 None, null이면 Some(None)으로 구분하기 위한 헬퍼
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+// update_task에 전달되는 부분 수정 내용 (None인 필드는 그대로 유지)
+#[derive(Clone, Serialize, Deserialize, Default)]
+struct TaskPatch {
+    text: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    due_at: Option<Option<i64>>, // null이면 마감 해제
+    reminder_offsets: Option<Vec<i64>>,
+    priority: Option<Priority>,
+    tags: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    recurrence: Option<Option<Recurrence>>, // null이면 반복 해제
+}
+
+impl TaskPatch {
+    fn validate(&self) -> AppResult<()> {
+        if let Some(text) = &self.text {
+            if text.trim().is_empty() {
+                return Err(AppError::invalid("task text must not be empty"));
+            }
+        }
+        if let Some(Some(due_at)) = self.due_at {
+            if !(0..=MAX_TIMESTAMP).contains(&due_at) {
+                return Err(AppError::invalid(format!("due date out of range: {due_at}")));
+            }
+        }
+        if let Some(offsets) = &self.reminder_offsets {
+            if offsets.iter().any(|o| *o < 0) {
+                return Err(AppError::invalid("reminder offsets must not be negative"));
+            }
+        }
+        if let Some(Some(recurrence)) = &self.recurrence {
+            recurrence.validate()?;
+        }
+        Ok(())
+    }
+
+    fn apply(&self, item: &mut TodoItem) {
+        if let Some(text) = &self.text {
+            item.text = text.trim().to_string();
+        }
+        // 마감/알림 설정이 바뀌면 알림을 다시 보낼 수 있도록 기록 초기화
+        if let Some(due_at) = self.due_at {
+            item.due_at = due_at;
+            item.reminders_fired.clear();
+        }
+        if let Some(offsets) = &self.reminder_offsets {
+            let mut offsets = offsets.clone();
+            offsets.sort_unstable();
+            offsets.dedup();
+            item.reminder_offsets = offsets;
+            item.reminders_fired.clear();
+        }
+        if let Some(priority) = self.priority {
+            item.priority = priority;
+        }
+        if let Some(tags) = &self.tags {
+            item.tags = normalize_tags(tags);
+        }
+        if let Some(recurrence) = &self.recurrence {
+            item.recurrence = recurrence.clone();
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Default)]
@@ -24,107 +353,1270 @@ struct StopwatchState {
     lap_totals_ms: Vec<u64>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-struct AppData {
-    v: u32,
-    tasks: Vec<TodoItem>,
-    stopwatch: Option<StopwatchState>,
+#[derive(Clone, Serialize, Deserialize)]
+struct AppData {
+    v: u32,
+    tasks: Vec<TodoItem>,
+    stopwatch: Option<StopwatchState>,
+    #[serde(default)]
+    next_id: u64, // 다음에 발급할 할 일 ID (단조 증가)
+    #[serde(default)]
+    lists: Vec<TaskList>, // 첫 번째 목록이 기본 목록 (항상 하나 이상 유지)
+    #[serde(default)]
+    history: history::History, // 실행 취소/다시 실행 기록 (내보내기에는 포함하지 않음)
+    #[serde(default)]
+    trash: Vec<TrashedTask>,
+    #[serde(default = "default_trash_retention_days")]
+    trash_retention_days: u32, // 0이면 자동 비우기 안 함
+    #[serde(default = "default_archive_after_days")]
+    archive_after_days: u32, // 완료 후 이 기간이 지나면 보관 파일로 이동 (0이면 자동 보관 안 함)
+    #[serde(default)]
+    compression: storage::Compression, // 데이터 파일·백업을 암호화하기 전에 적용할 압축
+}
+
+impl Default for AppData {
+    fn default() -> Self {
+        let mut data = Self {
+            v: migrations::CURRENT_VERSION,
+            tasks: Vec::new(),
+            stopwatch: None,
+            next_id: 1,
+            lists: Vec::new(),
+            history: history::History::default(),
+            trash: Vec::new(),
+            trash_retention_days: DEFAULT_TRASH_RETENTION_DAYS,
+            archive_after_days: DEFAULT_ARCHIVE_AFTER_DAYS,
+            compression: storage::Compression::default(),
+        };
+        data.ensure_lists();
+        data
+    }
+}
+
+impl AppData {
+    fn allocate_id(&mut self) -> u64 {
+        // 저장된 next_id가 기존 ID보다 작으면(구버전 데이터 등) 최대값 다음부터 발급
+        // (보관 파일의 ID는 로드·가져오기·복원 때 dedupe_task_ids가 next_id에 반영)
+        let max_id = self
+            .tasks
+            .iter()
+            .map(|t| t.id)
+            .chain(self.lists.iter().map(|l| l.id))
+            .chain(self.trash.iter().map(|t| t.task.id))
+            .max()
+            .unwrap_or(0);
+        let id = self.next_id.max(max_id + 1);
+        self.next_id = id + 1;
+        id
+    }
+
+    /// 중복된 할 일 ID를 새 ID로 교체하고 next_id를 보관 파일까지 포함한 최대 ID 다음으로 보정한다.
+    /// 예전 버전은 생성 시각(밀리초)을 ID로 써서 같은 밀리초에 추가된 항목끼리 충돌할 수 있었고,
+    /// 예전 데이터를 가져오거나 복원하면 next_id가 보관된 할 일의 ID보다 작아질 수 있다.
+    /// 변경이 있었으면 true를 반환한다.
+    fn dedupe_task_ids(&mut self, archived: &[TodoItem]) -> bool {
+        let max_id = self
+            .tasks
+            .iter()
+            .chain(self.trash.iter().map(|t| &t.task))
+            .chain(archived)
+            .map(|t| t.id)
+            .max()
+            .unwrap_or(0);
+        let mut changed = false;
+        if self.next_id <= max_id {
+            self.next_id = max_id + 1;
+            changed = true;
+        }
+
+        let mut next_id = self.next_id;
+        let mut seen = std::collections::HashSet::new();
+        for task in self.tasks.iter_mut().chain(self.trash.iter_mut().map(|t| &mut t.task)) {
+            // 보관 도중 중단되어 같은 할 일(생성 시각이 같음)이 보관 파일에도 남은 경우는 충돌이 아님
+            let clashes_archive = archived
+                .iter()
+                .any(|a| a.id == task.id && a.created_at != task.created_at);
+            if !seen.insert(task.id) || clashes_archive {
+                task.id = next_id;
+                next_id += 1;
+                seen.insert(task.id);
+                changed = true;
+            }
+        }
+        self.next_id = next_id;
+        changed
+    }
+
+    /// v1 데이터(목록 없음)를 기본 목록으로 옮기고, 존재하지 않는 목록을 가리키는 할 일도
+    /// 기본 목록으로 보낸다. 변경이 있었으면 true를 반환한다.
+    fn ensure_lists(&mut self) -> bool {
+        let mut changed = false;
+        if self.lists.is_empty() {
+            let id = self.allocate_id();
+            self.lists.push(TaskList {
+                id,
+                name: DEFAULT_LIST_NAME.to_string(),
+                archived: false,
+                created_at: now_secs(),
+            });
+            changed = true;
+        }
+        let default_id = self.lists[0].id;
+        for t in self.tasks.iter_mut() {
+            if !self.lists.iter().any(|l| l.id == t.list_id) {
+                t.list_id = default_id;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// 보관 기간이 지난 휴지통 항목을 영구 삭제한다. 변경이 있었으면 true를 반환한다.
+    fn purge_trash(&mut self, now: i64) -> bool {
+        if self.trash_retention_days == 0 {
+            return false;
+        }
+        let cutoff = now - self.trash_retention_days as i64 * 86400;
+        let before = self.trash.len();
+        self.trash.retain(|t| t.deleted_at > cutoff);
+        self.trash.len() != before
+    }
+
+    // 할 일을 휴지통으로 옮긴다 (실행 취소 기록용으로 옮긴 항목을 반환)
+    fn move_to_trash(&mut self, pos: usize) -> TrashedTask {
+        let trashed = TrashedTask {
+            task: self.tasks.remove(pos),
+            deleted_at: now_secs(),
+            position: pos,
+        };
+        self.trash.push(trashed.clone());
+        trashed
+    }
+
+    // 보관 파일에서 할 일을 꺼내 맨 뒤에 넣는다. 다른 할 일이 같은 ID를 쓰고 있으면 덮어쓰지 않음
+    fn unarchive(&mut self, archive: &mut ArchiveData, id: u64) -> AppResult<()> {
+        let idx = archive
+            .tasks
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| AppError::not_found(format!("task not in archive: {id}")))?;
+        if self.tasks.iter().chain(self.trash.iter().map(|t| &t.task)).any(|t| t.id == id) {
+            return Err(AppError::new(ErrorCode::Conflict, format!("task id already in use: {id}")));
+        }
+        let mut task = archive.tasks.remove(idx);
+        if !self.lists.iter().any(|l| l.id == task.list_id) {
+            task.list_id = self.default_list_id();
+        }
+        self.tasks.push(task);
+        Ok(())
+    }
+
+    fn list_mut(&mut self, list_id: u64) -> AppResult<&mut TaskList> {
+        self.lists
+            .iter_mut()
+            .find(|l| l.id == list_id)
+            .ok_or_else(|| AppError::not_found(format!("list not found: {list_id}")))
+    }
+
+    // 목록을 지정하지 않으면 보관되지 않은 첫 목록에 추가
+    fn default_list_id(&self) -> u64 {
+        self.lists
+            .iter()
+            .find(|l| !l.archived)
+            .unwrap_or(&self.lists[0])
+            .id
+    }
+
+    // list_id가 None이면 보관된 목록을 뺀 모든 목록 (보기·검색·통계 공통 규칙)
+    fn in_view(&self, task: &TodoItem, list_id: Option<u64>) -> bool {
+        match list_id {
+            Some(id) => task.list_id == id,
+            None => !self.lists.iter().any(|l| l.id == task.list_id && l.archived),
+        }
+    }
+
+    fn tasks_in(&self, list_id: Option<u64>) -> impl Iterator<Item = &TodoItem> {
+        self.tasks.iter().filter(move |t| self.in_view(t, list_id))
+    }
+}
+
+struct AppState(Mutex<AppData>);
+
+impl AppState {
+    // 잠금 모드에서 unlock 전이나 복구 모드에서는 데이터가 로드되지 않았으므로
+    // 모든 명령이 "locked" / 복구 모드 에러를 반환 (빈 데이터로 기존 파일을 덮어쓰지 않음)
+    fn data(&self, app: &tauri::AppHandle) -> AppResult<MutexGuard<'_, AppData>> {
+        storage::ensure_unlocked(app)?;
+        if app.state::<recovery::RecoveryState>().failure().is_some() {
+            return Err(AppError::new(ErrorCode::RecoveryMode, recovery::RECOVERY_MODE));
+        }
+        Ok(self.0.lock().unwrap())
+    }
+}
+
+// 보관 파일 내용 (storage::load_archive / save_archive로 암호화 저장)
+#[derive(Clone, Serialize, Deserialize)]
+struct ArchiveData {
+    v: u32,
+    tasks: Vec<TodoItem>,
+}
+
+impl Default for ArchiveData {
+    fn default() -> Self {
+        Self {
+            v: 1,
+            tasks: Vec::new(),
+        }
+    }
+}
+
+fn load_archive(app: &tauri::AppHandle) -> AppResult<ArchiveData> {
+    let archive = storage::load_archive(app, |r| serde_json::from_reader(r).context("archive parse error"))?;
+    Ok(archive.unwrap_or_default())
+}
+
+fn save_archive(app: &tauri::AppHandle, archive: &ArchiveData) -> AppResult<()> {
+    storage::save_archive(app, archive)
+}
+
+// 통계용 보관 할 일. 보관 도중 중단되어 양쪽에 남은 할 일은 현재 데이터 쪽만 센다
+fn archived_tasks_for_stats(app: &tauri::AppHandle, data: &AppData) -> AppResult<Vec<TodoItem>> {
+    let active: std::collections::HashSet<u64> = data.tasks.iter().map(|t| t.id).collect();
+    let mut archived = load_archive(app)?.tasks;
+    archived.retain(|t| !active.contains(&t.id));
+    Ok(archived)
+}
+
+/// 완료된 지 days일이 지난 할 일을 보관 파일로 옮기고 옮긴 개수를 반환한다.
+/// 보관 파일을 먼저 저장하므로 중간에 실패해도 할 일이 사라지지 않는다 (호출자가 persist).
+fn archive_completed(app: &tauri::AppHandle, data: &mut AppData, days: u32) -> AppResult<usize> {
+    if days == 0 {
+        return Ok(0);
+    }
+    let cutoff = now_secs() - days as i64 * 86400;
+    let is_old = |t: &TodoItem| t.completed && t.completed_at.is_some_and(|at| at <= cutoff);
+    let old: Vec<TodoItem> = data.tasks.iter().filter(|t| is_old(t)).cloned().collect();
+    if old.is_empty() {
+        return Ok(0);
+    }
+
+    let mut archive = load_archive(app)?;
+    archive.tasks.retain(|a| !old.iter().any(|t| t.id == a.id));
+    archive.tasks.extend(old.iter().cloned());
+    save_archive(app, &archive)?;
+
+    data.tasks.retain(|t| !is_old(t));
+    Ok(old.len())
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+// 저장은 백그라운드 작업자가 모아서 처리 (실패는 get_persist_status와 persist-failed 이벤트로 전달)
+fn persist(app: &tauri::AppHandle, data: AppData) {
+    app.state::<persistence::Persister>().schedule(data);
+}
+
+// 대기 중인 저장을 끝까지 쓴다 (창 닫기·앱 종료)
+fn flush_on_exit(app: &tauri::AppHandle) {
+    if let Err(e) = app.state::<persistence::Persister>().flush() {
+        eprintln!("final persist failed: {e}");
+    }
+}
+
+// 저장된 데이터를 읽어 상태에 넣는다 (시작 시, 잠금 해제 시)
+fn load_app_data(app: &tauri::AppHandle) -> AppResult<()> {
+    storage::finish_key_rotation(app)?;
+    let Some((mut loaded, migrated)) = storage::load_encrypted(app, migrations::load)
+        .map_err(|e| AppError::new(e.code, "failed to load stored data").with_details(e))?
+    else {
+        return Ok(());
+    };
+    // 스키마 마이그레이션, 구버전 데이터의 ID 충돌·목록 이전, 휴지통·완료 할 일 보관 정리 후 바로 저장
+    // 비단락 평가(|)로 모든 정리 작업을 수행
+    let archived = load_archive(app).map(|a| a.tasks).unwrap_or_else(|e| {
+        eprintln!("archive load failed: {e}");
+        Vec::new()
+    });
+    let mut changed =
+        migrated | loaded.dedupe_task_ids(&archived) | loaded.ensure_lists() | loaded.purge_trash(now_secs());
+    let days = loaded.archive_after_days;
+    match archive_completed(app, &mut loaded, days) {
+        Ok(moved) => changed |= moved > 0,
+        Err(e) => eprintln!("auto archive failed: {e}"),
+    }
+    if changed {
+        persist(app, loaded.clone());
+    }
+    let state = app.state::<AppState>();
+    let mut guard = state.0.lock().unwrap();
+    *guard = loaded;
+    Ok(())
+}
+
+// 로드에 실패하면 복구 모드로 들어가고 에러를 그대로 반환
+fn load_app_data_or_recover(app: &tauri::AppHandle) -> AppResult<()> {
+    load_app_data(app).inspect_err(|e| recovery::enter(app, e.clone()))
+}
+
+#[tauri::command]
+fn get_recovery_status(recovery: tauri::State<'_, recovery::RecoveryState>) -> Option<recovery::LoadFailure> {
+    recovery.failure()
+}
+
+// 격리한 파일을 되돌려 다시 읽어 본다 (실패하면 다시 격리되고 새 실패 정보로 복구 모드 유지)
+#[tauri::command]
+fn retry_load(
+    recovery: tauri::State<'_, recovery::RecoveryState>,
+    app: tauri::AppHandle,
+) -> AppResult<Option<recovery::LoadFailure>> {
+    let Some(failure) = recovery.failure() else {
+        return Ok(None);
+    };
+    let persister = app.state::<persistence::Persister>();
+    let _paused = persister.pause()?;
+    recovery::restore_quarantined(&app, &failure)?;
+    if load_app_data_or_recover(&app).is_ok() {
+        recovery.clear();
+    }
+    Ok(recovery.failure())
+}
+
+#[tauri::command]
+fn recover_from_snapshot(
+    id: String,
+    state: tauri::State<'_, AppState>,
+    recovery: tauri::State<'_, recovery::RecoveryState>,
+    app: tauri::AppHandle,
+) -> AppResult<AppData> {
+    if recovery.failure().is_none() {
+        return Err(AppError::invalid_state("not in recovery mode"));
+    }
+    let (mut restored, _) = storage::load_snapshot(&app, &id, migrations::load)?;
+    restored.dedupe_task_ids(&load_archive(&app)?.tasks);
+    restored.ensure_lists();
+    Ok(finish_recovery(&state, &recovery, &app, restored))
+}
+
+// 빈 데이터로 새로 시작 (격리된 파일은 quarantine/에 그대로 남음)
+#[tauri::command]
+fn start_fresh(
+    state: tauri::State<'_, AppState>,
+    recovery: tauri::State<'_, recovery::RecoveryState>,
+    app: tauri::AppHandle,
+) -> AppResult<AppData> {
+    if recovery.failure().is_none() {
+        return Err(AppError::invalid_state("not in recovery mode"));
+    }
+    storage::ensure_key_for_fresh_start(&app)?;
+    Ok(finish_recovery(&state, &recovery, &app, AppData::default()))
+}
+
+fn finish_recovery(
+    state: &tauri::State<'_, AppState>,
+    recovery: &tauri::State<'_, recovery::RecoveryState>,
+    app: &tauri::AppHandle,
+    data: AppData,
+) -> AppData {
+    *state.0.lock().unwrap() = data.clone();
+    persist(app, data.clone());
+    recovery.clear();
+    data
+}
+
+#[tauri::command]
+fn get_lock_status(app: tauri::AppHandle) -> AppResult<storage::LockStatus> {
+    storage::lock_status(&app)
+}
+
+// 잠금 모드를 켠다 (이미 불러온 데이터는 그대로 사용)
+#[tauri::command]
+fn enable_lock(passphrase: String, app: tauri::AppHandle) -> AppResult<storage::LockStatus> {
+    let persister = app.state::<persistence::Persister>();
+    let _paused = persister.pause()?;
+    storage::enable_lock(&app, &passphrase)?;
+    storage::lock_status(&app)
+}
+
+// 디스크에서 다시 읽어 상태에 넣을 때까지 백그라운드 저장을 멈춤
+#[tauri::command]
+fn unlock(passphrase: String, app: tauri::AppHandle) -> AppResult<storage::LockStatus> {
+    let persister = app.state::<persistence::Persister>();
+    let _paused = persister.pause()?;
+    storage::unlock(&app, &passphrase)?;
+    load_app_data_or_recover(&app)?;
+    storage::lock_status(&app)
+}
+
+// 대기 중인 변경을 저장한 뒤 다시 잠그고 메모리의 데이터도 비움
+#[tauri::command]
+fn lock(state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<storage::LockStatus> {
+    let persister = app.state::<persistence::Persister>();
+    let _paused = persister.pause()?;
+    storage::lock(&app)?;
+    *state.0.lock().unwrap() = AppData::default();
+    storage::lock_status(&app)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn change_password(
+    old_passphrase: String,
+    new_passphrase: String,
+    app: tauri::AppHandle,
+) -> AppResult<storage::LockStatus> {
+    // 잠긴 상태에서 바꾸면 키가 풀리므로 unlock처럼 데이터를 읽어 와야 빈 데이터로 덮어쓰지 않음
+    let was_locked = storage::is_locked(&app);
+    let persister = app.state::<persistence::Persister>();
+    let _paused = persister.pause()?;
+    storage::change_password(&app, &old_passphrase, &new_passphrase)?;
+    if was_locked {
+        load_app_data_or_recover(&app)?;
+    }
+    storage::lock_status(&app)
+}
+
+#[tauri::command]
+fn disable_lock(passphrase: String, app: tauri::AppHandle) -> AppResult<storage::LockStatus> {
+    let was_locked = storage::is_locked(&app);
+    let persister = app.state::<persistence::Persister>();
+    let _paused = persister.pause()?;
+    storage::disable_lock(&app, &passphrase)?;
+    if was_locked {
+        load_app_data_or_recover(&app)?;
+    }
+    storage::lock_status(&app)
+}
+
+// 데이터 키를 새로 만들어 저장된 파일을 모두 다시 암호화하고 새 키 식별자를 반환
+// (잠금 모드에서는 비밀번호 필요). 교체 중에는 상태를 잠그고 백그라운드 저장도 멈춰 예전 키로 덮어쓰지 않게 함
+#[tauri::command]
+fn rotate_key(
+    passphrase: Option<String>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<String> {
+    let data = state.data(&app)?;
+    let persister = app.state::<persistence::Persister>();
+    let _paused = persister.pause()?;
+    let key_id = storage::rotate_key(&app, passphrase.as_deref(), now_secs())?;
+    drop(data);
+    Ok(key_id)
+}
+
+#[tauri::command]
+fn get_persist_status(persister: tauri::State<'_, persistence::Persister>) -> persistence::PersistStatus {
+    persister.status()
+}
+
+#[tauri::command]
+fn get_key_status(app: tauri::AppHandle) -> AppResult<storage::KeyStatus> {
+    storage::key_status(&app)
+}
+
+#[tauri::command]
+fn set_key_policy(source: storage::KeySource, app: tauri::AppHandle) -> AppResult<storage::KeyStatus> {
+    let persister = app.state::<persistence::Persister>();
+    let _paused = persister.pause()?;
+    storage::set_key_policy(&app, source)?;
+    storage::key_status(&app)
+}
+
+// 키를 OS 키체인으로 옮기고 평문 키 파일 삭제 (이후 정책은 키체인 전용)
+#[tauri::command]
+fn migrate_key_to_keyring(app: tauri::AppHandle) -> AppResult<storage::KeyStatus> {
+    let persister = app.state::<persistence::Persister>();
+    let _paused = persister.pause()?;
+    storage::migrate_key_to_keyring(&app)?;
+    storage::key_status(&app)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn get_tasks(
+    sort: Option<TaskSort>,
+    tag: Option<String>,
+    list_id: Option<u64>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let data = state.data(&app)?;
+    let mut tasks: Vec<TodoItem> = data.tasks_in(list_id).cloned().collect();
+    drop(data);
+    if let Some(tag) = tag.as_deref().and_then(normalize_tag) {
+        tasks.retain(|t| t.tags.contains(&tag));
+    }
+    sort_tasks(&mut tasks, sort.unwrap_or_default());
+    Ok(tasks)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn add_task(
+    text: String,
+    priority: Option<Priority>,
+    list_id: Option<u64>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let list_id = match list_id {
+        Some(id) => data.list_mut(id)?.id,
+        None => data.default_list_id(),
+    };
+    let item = TodoItem {
+        id: data.allocate_id(),
+        text,
+        completed: false,
+        created_at: now_secs(),
+        completed_at: None,
+        due_at: None,
+        reminder_offsets: Vec::new(),
+        reminders_fired: Vec::new(),
+        priority: priority.unwrap_or_default(),
+        tags: BTreeSet::new(),
+        subtasks: Vec::new(),
+        progress: None,
+        recurrence: None,
+        list_id,
+    };
+
+    // 최신이 위로
+    data.tasks.insert(0, item.clone());
+    history::record(
+        &mut data,
+        "add_task",
+        history::Op::Tasks {
+            before: Vec::new(),
+            after: vec![(0, item)],
+        },
+    );
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
+}
+
+#[tauri::command]
+fn toggle_task(id: u64, state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    if let Some(pos) = data.tasks.iter().position(|t| t.id == id) {
+        let before = data.tasks[pos].clone();
+        let now = now_secs();
+        // 다음 회차 계산은 실패할 수 있으므로 데이터를 바꾸기 전에 먼저 구한다
+        let next_due = match &before.recurrence {
+            Some(recurrence) if !before.completed => {
+                Some(recurrence.next_occurrence(before.due_at.unwrap_or(now), now)?)
+            }
+            _ => None,
+        };
+        let mut recurring = None;
+        let t = &mut data.tasks[pos];
+        t.completed = !t.completed;
+        if t.completed {
+            t.completed_at = Some(now);
+            // 상위 할 일을 완료하면 남은 하위 할 일도 함께 완료 (완료 해제 시 하위 항목은 그대로)
+            for s in t.subtasks.iter_mut().filter(|s| !s.completed) {
+                s.completed = true;
+                s.completed_at = Some(now);
+            }
+            t.refresh_progress();
+
+            // 반복 할 일은 완료된 회차를 기록(통계)으로 남기고 다음 회차를 새로 만든다
+            if let (Some(recurrence), Some(next_due)) = (t.recurrence.take(), next_due) {
+                recurring = Some((recurrence, next_due));
+            }
+        } else {
+            t.completed_at = None;
+        }
+
+        let mut after = Vec::new();
+        if let Some((recurrence, next_due)) = recurring {
+            let item = next_recurring_item(&mut data, pos, recurrence, next_due, now);
+            data.tasks.insert(pos, item.clone());
+            after.push((pos, item));
+        }
+        let toggled_pos = pos + after.len();
+        after.push((toggled_pos, data.tasks[toggled_pos].clone()));
+        history::record(
+            &mut data,
+            "toggle_task",
+            history::Op::Tasks {
+                before: vec![(pos, before)],
+                after,
+            },
+        );
+    }
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
+}
+
+// 완료된 반복 할 일로부터 다음 회차 생성 (하위 할 일은 미완료 상태로 복사)
+fn next_recurring_item(data: &mut AppData, done_pos: usize, recurrence: Recurrence, next_due: i64, now: i64) -> TodoItem {
+    let done = data.tasks[done_pos].clone();
+    let mut subtasks = done.subtasks;
+    for s in subtasks.iter_mut() {
+        s.id = data.allocate_id();
+        s.completed = false;
+        s.created_at = now;
+        s.completed_at = None;
+    }
+    let mut item = TodoItem {
+        id: data.allocate_id(),
+        text: done.text,
+        completed: false,
+        created_at: now,
+        completed_at: None,
+        due_at: Some(next_due),
+        reminder_offsets: done.reminder_offsets,
+        reminders_fired: Vec::new(),
+        priority: done.priority,
+        tags: done.tags,
+        subtasks,
+        progress: None,
+        recurrence: Some(recurrence),
+        list_id: done.list_id,
+    };
+    item.refresh_progress();
+    item
+}
+
+#[tauri::command]
+fn delete_task(id: u64, state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    if let Some(pos) = data.tasks.iter().position(|t| t.id == id) {
+        let trashed = data.move_to_trash(pos);
+        history::record(&mut data, "delete_task", history::Op::Trash { items: vec![trashed] });
+    }
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
+}
+
+#[tauri::command]
+fn update_task(
+    id: u64,
+    patch: TaskPatch,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    patch.validate()?;
+
+    let mut data = state.data(&app)?;
+    let item = data
+        .tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or_else(|| AppError::not_found(format!("task not found: {id}")))?;
+    // id, created_at, completed_at은 건드리지 않아 통계 이력이 유지됨
+    patch.apply(item);
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn move_task(
+    id: u64,
+    to_index: usize,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let from = data
+        .tasks
+        .iter()
+        .position(|t| t.id == id)
+        .ok_or_else(|| AppError::not_found(format!("task not found: {id}")))?;
+    let item = data.tasks.remove(from);
+    // to_index는 같은 목록의 할 일 사이에서의 위치 (다른 목록 할 일의 자리는 그대로)
+    let slots: Vec<usize> = data
+        .tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.list_id == item.list_id)
+        .map(|(i, _)| i)
+        .collect();
+    let to = match slots.get(to_index) {
+        Some(&pos) => pos,
+        None => slots.last().map_or(from, |&pos| pos + 1),
+    };
+    data.tasks.insert(to, item);
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
+}
+
+// 전달된 ID들이 현재 차지하는 자리 안에서만 순서를 바꾼다.
+// 필터된 목록에서 드래그해도 보이지 않는 항목의 위치는 그대로 유지됨
+#[tauri::command]
+fn reorder_tasks(
+    ids: Vec<u64>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let mut slots = Vec::with_capacity(ids.len());
+    for id in &ids {
+        let pos = data
+            .tasks
+            .iter()
+            .position(|t| t.id == *id)
+            .ok_or_else(|| AppError::not_found(format!("task not found: {id}")))?;
+        if slots.contains(&pos) {
+            return Err(AppError::invalid(format!("duplicate task id: {id}")));
+        }
+        slots.push(pos);
+    }
+
+    let items: Vec<TodoItem> = slots.iter().map(|&pos| data.tasks[pos].clone()).collect();
+    slots.sort_unstable();
+    for (pos, item) in slots.into_iter().zip(items) {
+        data.tasks[pos] = item;
+    }
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
+}
+
+#[tauri::command]
+fn add_tags(
+    id: u64,
+    tags: Vec<String>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let item = data
+        .tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or_else(|| AppError::not_found(format!("task not found: {id}")))?;
+    item.tags.extend(normalize_tags(&tags));
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
+}
+
+#[tauri::command]
+fn remove_tags(
+    id: u64,
+    tags: Vec<String>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let item = data
+        .tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or_else(|| AppError::not_found(format!("task not found: {id}")))?;
+    for tag in normalize_tags(&tags) {
+        item.tags.remove(&tag);
+    }
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
 }
 
-impl Default for AppData {
-    fn default() -> Self {
-        Self {
-            v: 1,
-            tasks: Vec::new(),
-            stopwatch: None,
+// 모든 할 일에서 태그 이름 변경 (이미 새 이름을 가진 할 일은 하나로 합쳐짐)
+#[tauri::command]
+fn rename_tag(
+    from: String,
+    to: String,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let from = normalize_tag(&from).ok_or_else(|| AppError::invalid("tag must not be empty"))?;
+    let to = normalize_tag(&to).ok_or_else(|| AppError::invalid("tag must not be empty"))?;
+
+    let mut data = state.data(&app)?;
+    for t in data.tasks.iter_mut() {
+        if t.tags.remove(&from) {
+            t.tags.insert(to.clone());
         }
     }
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
 }
 
-struct AppState(Mutex<AppData>);
+#[tauri::command]
+fn list_tags(state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<Vec<TagCount>> {
+    let data = state.data(&app)?;
+    let mut counts: BTreeMap<&str, u32> = BTreeMap::new();
+    for tag in data.tasks.iter().flat_map(|t| t.tags.iter()) {
+        *counts.entry(tag).or_insert(0) += 1;
+    }
+    Ok(counts
+        .into_iter()
+        .map(|(name, count)| TagCount {
+            name: name.to_string(),
+            count,
+        })
+        .collect())
+}
 
-fn now_millis() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64
+#[tauri::command(rename_all = "snake_case")]
+fn add_subtask(
+    task_id: u64,
+    text: String,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err(AppError::invalid("subtask text must not be empty"));
+    }
+
+    let mut data = state.data(&app)?;
+    let id = data.allocate_id();
+    let item = data
+        .tasks
+        .iter_mut()
+        .find(|t| t.id == task_id)
+        .ok_or_else(|| AppError::not_found(format!("task not found: {task_id}")))?;
+    item.subtasks.push(Subtask {
+        id,
+        text,
+        completed: false,
+        created_at: now_secs(),
+        completed_at: None,
+    });
+    item.refresh_progress();
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
 }
 
-fn now_secs() -> i64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as i64
+#[tauri::command(rename_all = "snake_case")]
+fn toggle_subtask(
+    task_id: u64,
+    subtask_id: u64,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let item = data
+        .tasks
+        .iter_mut()
+        .find(|t| t.id == task_id)
+        .ok_or_else(|| AppError::not_found(format!("task not found: {task_id}")))?;
+    let sub = item.subtask_mut(subtask_id)?;
+    sub.completed = !sub.completed;
+    sub.completed_at = if sub.completed { Some(now_secs()) } else { None };
+    // 완료된 상위 할 일의 하위 항목을 다시 열면 상위 할 일도 진행중으로 되돌림
+    if !sub.completed && item.completed {
+        item.completed = false;
+        item.completed_at = None;
+    }
+    item.refresh_progress();
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
 }
 
-fn persist(app: &tauri::AppHandle, data: &AppData) {
-    match serde_json::to_vec(data) {
-        Ok(bytes) => {
-            if let Err(e) = storage::save_encrypted(app, &bytes) {
-                eprintln!("persist failed: {e}");
-            }
+#[tauri::command(rename_all = "snake_case")]
+fn delete_subtask(
+    task_id: u64,
+    subtask_id: u64,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let item = data
+        .tasks
+        .iter_mut()
+        .find(|t| t.id == task_id)
+        .ok_or_else(|| AppError::not_found(format!("task not found: {task_id}")))?;
+    item.subtasks.retain(|s| s.id != subtask_id);
+    item.refresh_progress();
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
+}
+
+// 하위 할 일 전체의 새 순서를 받는다 (목록에 없는 ID나 누락된 ID가 있으면 에러)
+#[tauri::command(rename_all = "snake_case")]
+fn reorder_subtasks(
+    task_id: u64,
+    subtask_ids: Vec<u64>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let item = data
+        .tasks
+        .iter_mut()
+        .find(|t| t.id == task_id)
+        .ok_or_else(|| AppError::not_found(format!("task not found: {task_id}")))?;
+    if subtask_ids.len() != item.subtasks.len() {
+        return Err(AppError::invalid("subtask id list does not match the task's subtasks"));
+    }
+    let mut reordered = Vec::with_capacity(subtask_ids.len());
+    for id in &subtask_ids {
+        if reordered.iter().any(|s: &Subtask| s.id == *id) {
+            return Err(AppError::invalid(format!("duplicate subtask id: {id}")));
         }
-        Err(e) => eprintln!("persist serialize failed: {e}"),
+        let sub = item
+            .subtasks
+            .iter()
+            .find(|s| s.id == *id)
+            .ok_or_else(|| AppError::not_found(format!("subtask not found: {id}")))?;
+        reordered.push(sub.clone());
     }
+    item.subtasks = reordered;
+    let tasks = data.tasks.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(tasks)
 }
 
-#[tauri::command]
-fn get_tasks(state: tauri::State<'_, AppState>) -> Vec<TodoItem> {
-    state.0.lock().unwrap().tasks.clone()
+#[tauri::command(rename_all = "snake_case")]
+fn get_lists(
+    include_archived: Option<bool>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TaskList>> {
+    let include_archived = include_archived.unwrap_or(false);
+    let lists = state
+        .data(&app)?
+        .lists
+        .iter()
+        .filter(|l| include_archived || !l.archived)
+        .cloned()
+        .collect();
+    Ok(lists)
 }
 
 #[tauri::command]
-fn add_task(text: String, state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> Vec<TodoItem> {
-    let mut data = state.0.lock().unwrap();
-    let item = TodoItem {
-        id: now_millis(),
-        text,
-        completed: false,
+fn create_list(
+    name: String,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TaskList>> {
+    let name = normalize_list_name(&name)?;
+    let mut data = state.data(&app)?;
+    let id = data.allocate_id();
+    data.lists.push(TaskList {
+        id,
+        name,
+        archived: false,
         created_at: now_secs(),
-        completed_at: None,
-    };
+    });
+    let lists = data.lists.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(lists)
+}
 
-    // 최신이 위로
-    data.tasks.insert(0, item);
+#[tauri::command(rename_all = "snake_case")]
+fn rename_list(
+    list_id: u64,
+    name: String,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TaskList>> {
+    let name = normalize_list_name(&name)?;
+    let mut data = state.data(&app)?;
+    data.list_mut(list_id)?.name = name;
+    let lists = data.lists.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(lists)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn set_list_archived(
+    list_id: u64,
+    archived: bool,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TaskList>> {
+    let mut data = state.data(&app)?;
+    data.list_mut(list_id)?.archived = archived;
+    let lists = data.lists.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(lists)
+}
+
+// 목록과 그 안의 할 일을 함께 삭제 (마지막 남은 목록은 삭제 불가)
+#[tauri::command(rename_all = "snake_case")]
+fn delete_list(
+    list_id: u64,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TaskList>> {
+    let mut data = state.data(&app)?;
+    let position = data
+        .lists
+        .iter()
+        .position(|l| l.id == list_id)
+        .ok_or_else(|| AppError::not_found(format!("list not found: {list_id}")))?;
+    if data.lists.len() == 1 {
+        return Err(AppError::invalid_state("cannot delete the last list"));
+    }
+    let list = data.lists.remove(position);
+    // 소속 할 일은 휴지통으로 (복원하면 기본 목록으로 들어감, 실행 취소하면 목록째 되살아남)
+    let mut items = Vec::new();
+    while let Some(pos) = data.tasks.iter().position(|t| t.list_id == list_id) {
+        items.push(data.move_to_trash(pos));
+    }
+    history::record(&mut data, "delete_list", history::Op::DeleteList { list, position, items });
+    let lists = data.lists.clone();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(lists)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+fn move_tasks_to_list(
+    ids: Vec<u64>,
+    list_id: u64,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    data.list_mut(list_id)?;
+    if let Some(id) = ids.iter().find(|id| !data.tasks.iter().any(|t| t.id == **id)) {
+        return Err(AppError::not_found(format!("task not found: {id}")));
+    }
+    for t in data.tasks.iter_mut().filter(|t| ids.contains(&t.id)) {
+        t.list_id = list_id;
+    }
     let tasks = data.tasks.clone();
     let snapshot = data.clone();
     drop(data);
-    persist(&app, &snapshot);
-    tasks
+    persist(&app, snapshot);
+    Ok(tasks)
+}
+
+// 실행 취소/다시 실행 결과 (UI는 할 일 목록과 스탑워치를 다시 그림)
+#[derive(Clone, Serialize)]
+struct UndoResult {
+    label: String,
+    tasks: Vec<TodoItem>,
+    stopwatch: Option<StopwatchState>,
+    history: history::HistoryStatus,
+}
+
+fn apply_history(
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+    step: fn(&mut AppData) -> AppResult<String>,
+) -> AppResult<UndoResult> {
+    let mut data = state.data(&app)?;
+    let label = step(&mut data)?;
+    let result = UndoResult {
+        label,
+        tasks: data.tasks.clone(),
+        stopwatch: data.stopwatch.clone(),
+        history: data.history.status(),
+    };
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(result)
 }
 
 #[tauri::command]
-fn toggle_task(id: u64, state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> Vec<TodoItem> {
-    let mut data = state.0.lock().unwrap();
-    if let Some(t) = data.tasks.iter_mut().find(|t| t.id == id) {
-        t.completed = !t.completed;
-        if t.completed {
-            t.completed_at = Some(now_secs());
-        } else {
-            t.completed_at = None;
-        }
+fn undo(state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<UndoResult> {
+    apply_history(state, app, history::undo)
+}
+
+#[tauri::command]
+fn redo(state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<UndoResult> {
+    apply_history(state, app, history::redo)
+}
+
+#[tauri::command]
+fn get_history_status(
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<history::HistoryStatus> {
+    Ok(state.data(&app)?.history.status())
+}
+
+// 할 일 검색 (list_id가 없으면 모든 목록, limit 기본값 50)
+#[tauri::command(rename_all = "snake_case")]
+fn search_tasks(
+    query: String,
+    list_id: Option<u64>,
+    limit: Option<usize>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<search::SearchHit>> {
+    let data = state.data(&app)?;
+    search::search(data.tasks_in(list_id), &query, limit.unwrap_or(50))
+}
+
+// 완료된 지 older_than_days일(기본값은 설정값)이 지난 할 일을 바로 보관
+#[tauri::command(rename_all = "snake_case")]
+fn archive_completed_tasks(
+    older_than_days: Option<u32>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let days = older_than_days.unwrap_or(data.archive_after_days);
+    if archive_completed(&app, &mut data, days)? > 0 {
+        let snapshot = data.clone();
+        persist(&app, snapshot);
     }
-    let tasks = data.tasks.clone();
+    Ok(data.tasks.clone())
+}
+
+#[tauri::command]
+fn set_archive_after_days(days: u32, state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<u32> {
+    let mut data = state.data(&app)?;
+    data.archive_after_days = days;
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(days)
+}
+
+// 다음 저장부터 적용 (이미 저장된 파일은 헤더에 기록된 방식으로 계속 읽힘)
+#[tauri::command]
+fn set_compression(
+    compression: storage::Compression,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<storage::Compression> {
+    let mut data = state.data(&app)?;
+    data.compression = compression;
     let snapshot = data.clone();
     drop(data);
-    persist(&app, &snapshot);
-    tasks
+    persist(&app, snapshot);
+    Ok(compression)
+}
+
+// 최근에 완료한 항목부터
+#[tauri::command(rename_all = "snake_case")]
+fn list_archived(
+    list_id: Option<u64>,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let data = state.data(&app)?;
+    let mut tasks = archived_tasks_for_stats(&app, &data)?;
+    tasks.retain(|t| data.in_view(t, list_id));
+    drop(data);
+    tasks.sort_by_key(|t| std::cmp::Reverse(t.completed_at));
+    Ok(tasks)
+}
+
+#[tauri::command]
+fn unarchive_task(
+    id: u64,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let mut archive = load_archive(&app)?;
+    data.unarchive(&mut archive, id)?;
+
+    // 현재 데이터를 먼저 저장한 뒤 보관 파일에서 제거 (중단되어도 할 일이 사라지지 않음)
+    let snapshot = data.clone();
+    persist(&app, snapshot);
+    app.state::<persistence::Persister>().flush()?;
+    save_archive(&app, &archive)?;
+    Ok(data.tasks.clone())
+}
+
+// 최근에 삭제한 항목부터
+#[tauri::command]
+fn list_trash(state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<Vec<TrashedTask>> {
+    let mut trash = state.data(&app)?.trash.clone();
+    trash.sort_by_key(|t| std::cmp::Reverse(t.deleted_at));
+    Ok(trash)
 }
 
 #[tauri::command]
-fn delete_task(id: u64, state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> Vec<TodoItem> {
-    let mut data = state.0.lock().unwrap();
-    data.tasks.retain(|t| t.id != id);
+fn restore_task(
+    id: u64,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Vec<TodoItem>> {
+    let mut data = state.data(&app)?;
+    let idx = data
+        .trash
+        .iter()
+        .position(|t| t.task.id == id)
+        .ok_or_else(|| AppError::not_found(format!("task not in trash: {id}")))?;
+    let TrashedTask { mut task, position, .. } = data.trash.remove(idx);
+    // 목록이 삭제되었으면 기본 목록으로
+    if !data.lists.iter().any(|l| l.id == task.list_id) {
+        task.list_id = data.default_list_id();
+    }
+    let pos = position.min(data.tasks.len());
+    data.tasks.insert(pos, task);
     let tasks = data.tasks.clone();
     let snapshot = data.clone();
     drop(data);
-    persist(&app, &snapshot);
-    tasks
+    persist(&app, snapshot);
+    Ok(tasks)
+}
+
+#[tauri::command]
+fn empty_trash(state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<Vec<TrashedTask>> {
+    let mut data = state.data(&app)?;
+    data.trash.clear();
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(Vec::new())
+}
+
+// 휴지통 보관 기간 변경 (0이면 자동 비우기 안 함), 바로 적용
+#[tauri::command]
+fn set_trash_retention(days: u32, state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<u32> {
+    let mut data = state.data(&app)?;
+    data.trash_retention_days = days;
+    data.purge_trash(now_secs());
+    let snapshot = data.clone();
+    drop(data);
+    persist(&app, snapshot);
+    Ok(days)
 }
 
 #[tauri::command]
-fn get_stopwatch_state(state: tauri::State<'_, AppState>) -> Option<StopwatchState> {
-    state.0.lock().unwrap().stopwatch.clone()
+fn get_stopwatch_state(
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<Option<StopwatchState>> {
+    Ok(state.data(&app)?.stopwatch.clone())
 }
 
 #[tauri::command]
@@ -132,24 +1624,37 @@ fn set_stopwatch_state(
     stopwatch: StopwatchState,
     state: tauri::State<'_, AppState>,
     app: tauri::AppHandle,
-) -> Option<StopwatchState> {
-    let mut data = state.0.lock().unwrap();
-    data.stopwatch = Some(stopwatch);
+) -> AppResult<Option<StopwatchState>> {
+    let mut data = state.data(&app)?;
+    let before = data.stopwatch.replace(stopwatch);
     let out = data.stopwatch.clone();
+    history::record(
+        &mut data,
+        "set_stopwatch_state",
+        history::Op::Stopwatch {
+            before,
+            after: out.clone(),
+        },
+    );
     let snapshot = data.clone();
     drop(data);
-    persist(&app, &snapshot);
-    out
+    persist(&app, snapshot);
+    Ok(out)
 }
 
 #[tauri::command]
-fn clear_stopwatch_state(state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> bool {
-    let mut data = state.0.lock().unwrap();
-    data.stopwatch = None;
+fn clear_stopwatch_state(state: tauri::State<'_, AppState>, app: tauri::AppHandle) -> AppResult<bool> {
+    let mut data = state.data(&app)?;
+    let before = data.stopwatch.take();
+    history::record(
+        &mut data,
+        "clear_stopwatch_state",
+        history::Op::Stopwatch { before, after: None },
+    );
     let snapshot = data.clone();
     drop(data);
-    persist(&app, &snapshot);
-    true
+    persist(&app, snapshot);
+    Ok(true)
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -157,12 +1662,18 @@ async fn export_data(
     state: tauri::State<'_, AppState>,
     app: tauri::AppHandle,
     file_path: String,
-) -> Result<String, String> {
-    let data = state.0.lock().unwrap().clone();
-    let bytes = serde_json::to_vec(&data).map_err(|e| format!("serialize error: {e}"))?;
-
+    list_id: Option<u64>,
+    passphrase: Option<String>,
+) -> AppResult<String> {
+    let mut data = history::without_history(&*state.data(&app)?);
+    // 목록을 지정하면 그 목록과 소속 할 일만 내보냄
+    if let Some(list_id) = list_id {
+        data.list_mut(list_id)?;
+        data.lists.retain(|l| l.id == list_id);
+        data.tasks.retain(|t| t.list_id == list_id);
+    }
     let path = std::path::PathBuf::from(file_path);
-    storage::export_backup(&app, &path, &bytes)?;
+    storage::export_backup(&app, &path, &data, data.compression, passphrase.as_deref())?;
     Ok(path.to_string_lossy().to_string())
 }
 
@@ -171,20 +1682,68 @@ async fn import_data(
     state: tauri::State<'_, AppState>,
     app: tauri::AppHandle,
     file_path: String,
-) -> Result<AppData, String> {
+    passphrase: Option<String>,
+) -> AppResult<AppData> {
     let path = std::path::PathBuf::from(file_path);
 
-    let bytes = storage::import_backup(&app, &path)?;
-    let imported: AppData = serde_json::from_slice(&bytes).map_err(|e| format!("parse error: {e}"))?;
+    let (mut imported, _) = storage::import_backup(&app, &path, passphrase.as_deref(), migrations::load)?;
+    imported.dedupe_task_ids(&load_archive(&app)?.tasks);
+    imported.ensure_lists();
+    replace_app_data(&state, &app, imported, "import_data")
+}
+
+// 전체 데이터를 교체한다 (가져오기·스냅샷 복원).
+// 기존 실행 취소 기록은 유지하고 교체 자체도 되돌릴 수 있게 기록
+fn replace_app_data(
+    state: &tauri::State<'_, AppState>,
+    app: &tauri::AppHandle,
+    mut incoming: AppData,
+    label: &str,
+) -> AppResult<AppData> {
+    incoming.history = history::History::default();
 
-    // 상태 업데이트
-    let mut current = state.0.lock().unwrap();
-    *current = imported.clone();
+    let mut current = state.data(app)?;
+    let before = history::without_history(&current);
+    let history = std::mem::take(&mut current.history);
+    *current = incoming.clone();
+    current.history = history;
+    history::record(
+        &mut current,
+        label,
+        history::Op::Replace {
+            before: Box::new(before),
+            after: Box::new(incoming.clone()),
+        },
+    );
+    let snapshot = current.clone();
     drop(current);
 
     // 즉시 저장
-    persist(&app, &imported);
-    Ok(imported)
+    persist(app, snapshot);
+    Ok(incoming)
+}
+
+#[tauri::command]
+fn list_snapshots(app: tauri::AppHandle) -> AppResult<Vec<storage::SnapshotInfo>> {
+    storage::list_snapshots(&app)
+}
+
+#[tauri::command]
+fn restore_snapshot(
+    id: String,
+    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> AppResult<AppData> {
+    let (mut restored, _) = storage::load_snapshot(&app, &id, migrations::load)?;
+    restored.dedupe_task_ids(&load_archive(&app)?.tasks);
+    restored.ensure_lists();
+    replace_app_data(&state, &app, restored, "restore_snapshot")
+}
+
+#[tauri::command]
+fn delete_snapshot(id: String, app: tauri::AppHandle) -> AppResult<Vec<storage::SnapshotInfo>> {
+    storage::delete_snapshot(&app, &id)?;
+    storage::list_snapshots(&app)
 }
 
 // --- 통계 관련 구조체 ---
@@ -196,6 +1755,8 @@ struct DailyStats {
     focus_time_ms: u64, // 스탑워치 사용 시간 (밀리초)
     lap_count: u32,
     avg_lap_time_ms: Option<u64>, // 평균 Lap 시간
+    completed_by_tag: BTreeMap<String, u32>, // 태그별 완료 수 (태그 없는 할 일은 제외)
+    subtasks_completed: u32, // 하위 할 일 완료 수 (tasks_completed와 별도 집계)
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -246,27 +1807,45 @@ fn get_date_range(start_date: &str, end_date: &str) -> Vec<String> {
     dates
 }
 
-fn compute_daily_stats(data: &AppData, date: &str) -> DailyStats {
+// list_id가 None이면 보관된 목록을 뺀 모든 목록 합산 (스탑워치 통계는 목록과 무관)
+// archived는 보관 파일의 할 일 (archived_tasks_for_stats)
+fn compute_daily_stats(
+    data: &AppData,
+    archived: &[TodoItem],
+    date: &str,
+    list_id: Option<u64>,
+) -> DailyStats {
     let start_ts = date_to_timestamp(date);
     let end_ts = start_ts + 86400; // 다음 날 자정 전까지
+    let tasks = || {
+        data.tasks_in(list_id)
+            .chain(archived.iter().filter(move |t| data.in_view(t, list_id)))
+    };
 
-    let tasks_completed = data
-        .tasks
-        .iter()
+    let completed: Vec<&TodoItem> = tasks()
         .filter(|t| {
             t.completed
                 && t.completed_at.is_some()
                 && t.completed_at.unwrap() >= start_ts
                 && t.completed_at.unwrap() < end_ts
         })
-        .count() as u32;
+        .collect();
+    let tasks_completed = completed.len() as u32;
 
-    let tasks_created = data
-        .tasks
-        .iter()
+    let mut completed_by_tag = BTreeMap::new();
+    for tag in completed.iter().flat_map(|t| t.tags.iter()) {
+        *completed_by_tag.entry(tag.clone()).or_insert(0) += 1;
+    }
+
+    let tasks_created = tasks()
         .filter(|t| t.created_at >= start_ts && t.created_at < end_ts)
         .count() as u32;
 
+    let subtasks_completed = tasks()
+        .flat_map(|t| t.subtasks.iter())
+        .filter(|s| s.completed && s.completed_at.is_some_and(|at| at >= start_ts && at < end_ts))
+        .count() as u32;
+
     // 스탑워치 통계는 현재 상태만 있으므로 간단히 처리
     let (focus_time_ms, lap_count, avg_lap_time_ms) = if let Some(sw) = &data.stopwatch {
         let lap_count = sw.lap_totals_ms.len() as u32;
@@ -288,24 +1867,32 @@ fn compute_daily_stats(data: &AppData, date: &str) -> DailyStats {
         focus_time_ms,
         lap_count,
         avg_lap_time_ms,
+        completed_by_tag,
+        subtasks_completed,
     }
 }
 
 #[tauri::command(rename_all = "snake_case")]
 fn get_daily_stats(
     date: String,
+    list_id: Option<u64>,
     state: tauri::State<'_, AppState>,
-) -> Result<DailyStats, String> {
-    let data = state.0.lock().unwrap();
-    Ok(compute_daily_stats(&data, &date))
+    app: tauri::AppHandle,
+) -> AppResult<DailyStats> {
+    let data = state.data(&app)?;
+    let archived = archived_tasks_for_stats(&app, &data)?;
+    Ok(compute_daily_stats(&data, &archived, &date, list_id))
 }
 
 #[tauri::command(rename_all = "snake_case")]
 fn get_weekly_stats(
     start_date: String,
+    list_id: Option<u64>,
     state: tauri::State<'_, AppState>,
-) -> Result<WeeklyStats, String> {
-    let data = state.0.lock().unwrap();
+    app: tauri::AppHandle,
+) -> AppResult<WeeklyStats> {
+    let data = state.data(&app)?;
+    let archived = archived_tasks_for_stats(&app, &data)?;
     let dates = get_date_range(&start_date, &timestamp_to_date(date_to_timestamp(&start_date) + 6 * 86400));
     let end_date = dates.last().unwrap().clone();
 
@@ -316,7 +1903,7 @@ fn get_weekly_stats(
     let mut total_laps = 0u32;
 
     for date in &dates {
-        let stats = compute_daily_stats(&data, date);
+        let stats = compute_daily_stats(&data, &archived, date, list_id);
         total_completed += stats.tasks_completed;
         total_created += stats.tasks_created;
         total_focus_ms += stats.focus_time_ms;
@@ -347,14 +1934,17 @@ async fn export_stats_csv(
     start_date: String,
     end_date: String,
     file_path: Option<String>,
+    list_id: Option<u64>,
     state: tauri::State<'_, AppState>,
-) -> Result<String, String> {
-    let data = state.0.lock().unwrap();
+    app: tauri::AppHandle,
+) -> AppResult<String> {
+    let data = state.data(&app)?;
+    let archived = archived_tasks_for_stats(&app, &data)?;
     let dates = get_date_range(&start_date, &end_date);
     let mut csv = String::from("날짜,완료된 할 일,생성된 할 일,집중 시간(분),Lap 수,평균 Lap 시간(초)\n");
 
     for date in dates {
-        let stats = compute_daily_stats(&data, &date);
+        let stats = compute_daily_stats(&data, &archived, &date, list_id);
         let focus_min = stats.focus_time_ms / 60000;
         let avg_lap_sec = stats.avg_lap_time_ms.map(|ms| ms / 1000).unwrap_or(0);
         csv.push_str(&format!(
@@ -375,24 +1965,23 @@ async fn export_stats_csv(
         std::path::PathBuf::from(default_name)
     };
     
-    std::fs::write(&path, csv.as_bytes()).map_err(|e| format!("CSV write error: {e}"))?;
+    std::fs::write(&path, csv.as_bytes()).context("CSV write error")?;
     Ok(path.to_string_lossy().to_string())
 }
 
 fn main() {
     tauri::Builder::default()
         .manage(AppState(Mutex::new(AppData::default())))
+        .manage(recovery::RecoveryState::default())
+        .manage(persistence::Persister::default())
         .setup(|app| {
-            if let Ok(Some(bytes)) = storage::load_encrypted(&app.handle()) {
-                match serde_json::from_slice::<AppData>(&bytes) {
-                    Ok(loaded) => {
-                        let state = app.state::<AppState>();
-                        let mut guard = state.0.lock().unwrap();
-                        *guard = loaded;
-                    }
-                    Err(e) => eprintln!("failed to parse stored data: {e}"),
-                }
+            persistence::start(app.handle());
+            // 잠금 모드면 unlock 명령에서 불러옴. 읽지 못하면 빈 상태로 시작하지 않고 복구 모드로
+            if !storage::is_locked(&app.handle()) {
+                let _ = load_app_data_or_recover(&app.handle());
             }
+            // 저장된 상태를 읽은 뒤에 시작해야 재시작 전에 설정한 알림도 이어서 동작
+            scheduler::start(app.handle());
             Ok(())
         })
         .on_window_event(|event| {
@@ -400,7 +1989,8 @@ fn main() {
             // 최소화 이벤트를 포함한 모든 이벤트를 안전하게 처리
             match event.event() {
                 WindowEvent::CloseRequested { .. } => {
-                    // 창 닫기 이벤트 처리
+                    // 창을 닫기 전에 대기 중인 변경을 저장
+                    flush_on_exit(&event.window().app_handle());
                 }
                 WindowEvent::Resized { .. } => {
                     // 크기 변경 이벤트 처리
@@ -412,10 +2002,58 @@ fn main() {
             }
         })
         .invoke_handler(tauri::generate_handler![
+            get_recovery_status,
+            retry_load,
+            recover_from_snapshot,
+            start_fresh,
+            get_lock_status,
+            enable_lock,
+            unlock,
+            lock,
+            change_password,
+            disable_lock,
+            rotate_key,
+            get_key_status,
+            set_key_policy,
+            migrate_key_to_keyring,
+            get_persist_status,
             get_tasks,
             add_task,
             toggle_task,
             delete_task,
+            update_task,
+            move_task,
+            reorder_tasks,
+            add_tags,
+            remove_tags,
+            rename_tag,
+            list_tags,
+            add_subtask,
+            toggle_subtask,
+            delete_subtask,
+            reorder_subtasks,
+            get_lists,
+            create_list,
+            rename_list,
+            set_list_archived,
+            delete_list,
+            move_tasks_to_list,
+            search_tasks,
+            undo,
+            redo,
+            get_history_status,
+            list_snapshots,
+            restore_snapshot,
+            delete_snapshot,
+            archive_completed_tasks,
+            set_archive_after_days,
+            set_compression,
+            list_archived,
+            unarchive_task,
+            list_trash,
+            restore_task,
+            empty_trash,
+            set_trash_retention,
             get_stopwatch_state,
             set_stopwatch_state,
             clear_stopwatch_state,
@@ -425,6 +2063,97 @@ fn main() {
             get_weekly_stats,
             export_stats_csv
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
\ No newline at end of file
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                flush_on_exit(app);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(year: i32, month: u32, day: u32) -> i64 {
+        chrono::NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp()
+    }
+
+    #[test]
+    fn import_then_unarchive_keeps_both_tasks() {
+        let mut archive = ArchiveData::default();
+        archive.tasks.push(TodoItem::for_test(5, "archived", 100));
+
+        // 보관 파일보다 오래된 백업: 같은 ID를 다른 할 일이 쓰고 next_id도 낮음
+        let mut imported = AppData {
+            next_id: 3,
+            ..AppData::default()
+        };
+        imported.tasks.push(TodoItem::for_test(5, "imported", 200));
+        assert!(imported.dedupe_task_ids(&archive.tasks));
+        imported.ensure_lists();
+        let renumbered = imported.tasks[0].id;
+        assert_ne!(renumbered, 5);
+        assert!(imported.allocate_id() > 5);
+
+        imported.unarchive(&mut archive, 5).unwrap();
+        let texts: Vec<(u64, &str)> = imported.tasks.iter().map(|t| (t.id, t.text.as_str())).collect();
+        assert_eq!(texts, vec![(renumbered, "imported"), (5, "archived")]);
+        assert!(archive.tasks.is_empty());
+    }
+
+    #[test]
+    fn dedupe_keeps_task_left_in_both_files() {
+        // 보관 도중 중단되어 같은 할 일이 양쪽에 남은 경우
+        let archived = vec![TodoItem::for_test(5, "same", 100)];
+        let mut data = AppData::default();
+        data.tasks.push(TodoItem::for_test(5, "same", 100));
+        data.dedupe_task_ids(&archived);
+        assert_eq!(data.tasks[0].id, 5);
+        assert!(data.next_id > 5);
+    }
+
+    #[test]
+    fn unarchive_rejects_id_in_use() {
+        let mut archive = ArchiveData::default();
+        archive.tasks.push(TodoItem::for_test(5, "archived", 100));
+        let mut data = AppData::default();
+        data.tasks.push(TodoItem::for_test(5, "active", 200));
+
+        let err = data.unarchive(&mut archive, 5).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        assert_eq!(data.tasks.len(), 1);
+        assert_eq!(data.tasks[0].text, "active");
+        assert_eq!(archive.tasks.len(), 1);
+    }
+
+    #[test]
+    fn monthly_uses_current_month_when_day_is_ahead() {
+        let rule = Recurrence::Monthly { day: 20 };
+        assert_eq!(rule.next_after(ts(2024, 3, 5)).unwrap(), ts(2024, 3, 20));
+        assert_eq!(rule.next_after(ts(2024, 3, 20)).unwrap(), ts(2024, 4, 20));
+        assert_eq!(rule.next_after(ts(2024, 12, 25)).unwrap(), ts(2025, 1, 20));
+    }
+
+    #[test]
+    fn monthly_clamps_to_month_length() {
+        let rule = Recurrence::Monthly { day: 31 };
+        assert_eq!(rule.next_after(ts(2024, 2, 10)).unwrap(), ts(2024, 2, 29));
+        assert_eq!(rule.next_after(ts(2024, 2, 29)).unwrap(), ts(2024, 3, 31));
+        assert_eq!(rule.next_after(ts(2024, 4, 30)).unwrap(), ts(2024, 5, 31));
+    }
+
+    #[test]
+    fn out_of_range_due_is_an_error() {
+        let rule = Recurrence::Daily { interval: 1 };
+        assert!(rule.next_after(i64::MAX).is_err());
+        assert!(rule.next_occurrence(MAX_TIMESTAMP, 0).is_err());
+        assert!(Recurrence::Daily { interval: u32::MAX }.validate().is_err());
+    }
+}
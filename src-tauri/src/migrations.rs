@@ -0,0 +1,189 @@
+use crate::error::{AppError, AppResult, Context, ErrorCode};
+use crate::{AppData, DEFAULT_ARCHIVE_AFTER_DAYS, DEFAULT_TRASH_RETENTION_DAYS};
+use serde_json::{json, Map, Value};
+use std::io::Read;
+
+// 스키마 버전별 마이그레이션. STEPS[i]는 v(i+1) → v(i+2)
+// 새 필드를 추가하면 단계를 하나 더하고 그 단계에서 기본값을 명시적으로 채운다
+const STEPS: [fn(&mut Map<String, Value>); 3] = [v1_to_v2, v2_to_v3, v3_to_v4];
+
+/// 현재 AppData 스키마 버전
+pub const CURRENT_VERSION: u32 = STEPS.len() as u32 + 1;
+
+/// 복호화한 AppData JSON을 현재 스키마로 올린 뒤 읽는다.
+/// 마이그레이션이 실행되었으면 true를 함께 반환하므로 호출자가 바로 저장하면 된다.
+pub fn load(reader: &mut dyn Read) -> AppResult<(AppData, bool)> {
+    let mut value: Value = serde_json::from_reader(reader).context("parse error")?;
+    let from = migrate(&mut value)?;
+    let data = serde_json::from_value(value).context(&format!("parse error (schema v{CURRENT_VERSION})"))?;
+    Ok((data, from < CURRENT_VERSION))
+}
+
+// 원래 버전을 반환 (v가 없으면 최초 형식인 v1로 취급)
+fn migrate(value: &mut Value) -> AppResult<u32> {
+    let obj = value
+        .as_object_mut()
+        .ok_or_else(|| AppError::new(ErrorCode::Corrupted, "data is not a JSON object"))?;
+    let from = match obj.get("v") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| AppError::new(ErrorCode::Corrupted, format!("invalid schema version: {v}")))?,
+    };
+    if from == 0 {
+        return Err(AppError::new(ErrorCode::Corrupted, "invalid schema version: 0"));
+    }
+    if from > CURRENT_VERSION {
+        return Err(AppError::new(ErrorCode::UnsupportedVersion, format!(
+            "data was saved by a newer version of the app (schema v{from}, this version supports up to v{CURRENT_VERSION}); please update the app"
+        )));
+    }
+
+    for (i, step) in STEPS.iter().enumerate().skip(from as usize - 1) {
+        step(obj);
+        obj.insert("v".to_string(), json!(i + 2));
+    }
+    Ok(from)
+}
+
+fn set_default(obj: &mut Map<String, Value>, key: &str, value: Value) {
+    obj.entry(key).or_insert(value);
+}
+
+fn tasks_mut(obj: &mut Map<String, Value>) -> impl Iterator<Item = &mut Map<String, Value>> {
+    obj.get_mut("tasks")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+// v2: 목록, 단조 증가 ID, 할 일의 마감·알림·우선순위·태그·하위 할 일·반복
+// (목록 생성과 ID 충돌 정리는 로드 후 ensure_lists / dedupe_task_ids가 담당)
+fn v1_to_v2(obj: &mut Map<String, Value>) {
+    set_default(obj, "tasks", json!([]));
+    set_default(obj, "stopwatch", Value::Null);
+    set_default(obj, "next_id", json!(0));
+    set_default(obj, "lists", json!([]));
+    for task in tasks_mut(obj) {
+        set_default(task, "due_at", Value::Null);
+        set_default(task, "reminder_offsets", json!([]));
+        set_default(task, "reminders_fired", json!([]));
+        set_default(task, "priority", json!("none"));
+        set_default(task, "tags", json!([]));
+        set_default(task, "subtasks", json!([]));
+        set_default(task, "progress", Value::Null);
+        set_default(task, "recurrence", Value::Null);
+        set_default(task, "list_id", json!(0));
+    }
+}
+
+// v3: 실행 취소 기록, 휴지통, 자동 보관 설정
+fn v2_to_v3(obj: &mut Map<String, Value>) {
+    set_default(obj, "history", json!({ "undo": [], "redo": [] }));
+    set_default(obj, "trash", json!([]));
+    set_default(obj, "trash_retention_days", json!(DEFAULT_TRASH_RETENTION_DAYS));
+    set_default(obj, "archive_after_days", json!(DEFAULT_ARCHIVE_AFTER_DAYS));
+}
+
+// v4: 저장 전 압축 설정
+fn v3_to_v4(obj: &mut Map<String, Value>) {
+    set_default(obj, "compression", json!("zstd"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = include_str!("../fixtures/migrations/v1.json");
+    const V2: &str = include_str!("../fixtures/migrations/v2.json");
+    const V3: &str = include_str!("../fixtures/migrations/v3.json");
+
+    fn load_str(raw: &str) -> AppResult<(AppData, bool)> {
+        load(&mut raw.as_bytes())
+    }
+
+    fn to_json(data: &AppData) -> Value {
+        serde_json::to_value(data).unwrap()
+    }
+
+    #[test]
+    fn v1_gets_every_later_field() {
+        let (data, migrated) = load_str(V1).unwrap();
+        assert!(migrated);
+        assert_eq!(data.v, CURRENT_VERSION);
+        assert_eq!(data.tasks.len(), 2);
+        let task = &data.tasks[0];
+        assert_eq!(task.id, 1700000000123);
+        assert_eq!(task.text, "장보기");
+        assert_eq!(task.completed_at, Some(1700003600));
+        assert!(task.due_at.is_none() && task.recurrence.is_none());
+        assert!(task.tags.is_empty() && task.subtasks.is_empty() && task.reminder_offsets.is_empty());
+        assert_eq!(task.list_id, 0); // 로드 후 ensure_lists가 기본 목록으로 옮김
+        assert_eq!(data.stopwatch.as_ref().unwrap().lap_totals_ms, vec![60000, 125000]);
+        assert_eq!(data.next_id, 0);
+        assert!(data.lists.is_empty() && data.trash.is_empty());
+        assert_eq!(data.trash_retention_days, DEFAULT_TRASH_RETENTION_DAYS);
+        assert_eq!(data.archive_after_days, DEFAULT_ARCHIVE_AFTER_DAYS);
+        assert_eq!(to_json(&data)["compression"], json!("zstd"));
+        assert_eq!(to_json(&data)["tasks"][0]["priority"], json!("none"));
+    }
+
+    #[test]
+    fn v2_keeps_fields_and_gets_v3_v4_defaults() {
+        let (data, migrated) = load_str(V2).unwrap();
+        assert!(migrated);
+        assert_eq!(data.v, CURRENT_VERSION);
+        let task = &data.tasks[0];
+        assert_eq!(task.due_at, Some(1700500000));
+        assert_eq!(task.reminder_offsets, vec![3600]);
+        assert!(task.tags.contains("finance"));
+        assert_eq!(task.subtasks.len(), 1);
+        assert_eq!(task.list_id, 1);
+        assert_eq!(to_json(&data)["tasks"][0]["recurrence"], json!({ "freq": "monthly", "day": 20 }));
+        assert_eq!(data.next_id, 5);
+        assert_eq!(data.lists.len(), 1);
+        assert!(data.trash.is_empty());
+        assert_eq!(data.trash_retention_days, DEFAULT_TRASH_RETENTION_DAYS);
+        assert_eq!(data.archive_after_days, DEFAULT_ARCHIVE_AFTER_DAYS);
+        assert_eq!(to_json(&data)["history"], json!({ "undo": [], "redo": [] }));
+        assert_eq!(to_json(&data)["compression"], json!("zstd"));
+    }
+
+    #[test]
+    fn v3_keeps_settings_and_gets_compression() {
+        let (data, migrated) = load_str(V3).unwrap();
+        assert!(migrated);
+        assert_eq!(data.v, CURRENT_VERSION);
+        assert_eq!(data.trash.len(), 1);
+        assert_eq!(data.trash[0].task.id, 5);
+        assert_eq!(data.trash_retention_days, 7);
+        assert_eq!(data.archive_after_days, 0);
+        assert_eq!(to_json(&data)["compression"], json!("zstd"));
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let raw = json!({ "v": CURRENT_VERSION + 1, "tasks": [], "stopwatch": null }).to_string();
+        let err = load_str(&raw).err().unwrap();
+        assert_eq!(err.code, ErrorCode::UnsupportedVersion);
+    }
+
+    #[test]
+    fn migrating_twice_is_a_no_op() {
+        for fixture in [V1, V2, V3] {
+            let (once, _) = load_str(fixture).unwrap();
+            let saved = serde_json::to_string(&once).unwrap();
+            let (twice, migrated) = load_str(&saved).unwrap();
+            assert!(!migrated);
+            assert_eq!(to_json(&once), to_json(&twice));
+
+            let mut value: Value = serde_json::from_str(fixture).unwrap();
+            migrate(&mut value).unwrap();
+            let migrated_once = value.clone();
+            assert_eq!(migrate(&mut value).unwrap(), CURRENT_VERSION);
+            assert_eq!(value, migrated_once);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::error::{AppError, AppResult};
+use crate::{now_secs, storage, AppData};
+use serde::Serialize;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+// 첫 변경 후 이 시간 동안 들어온 변경을 모아 한 번에 저장 (스탑워치처럼 자주 저장하는 명령용)
+const DEBOUNCE: Duration = Duration::from_millis(500);
+const PERSIST_FAILED_EVENT: &str = "persist-failed";
+
+#[derive(Clone, Serialize)]
+pub struct PersistFailure {
+    error: AppError,
+    at: i64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PersistStatus {
+    pending: bool, // 아직 디스크에 쓰지 않은 변경이 있음
+    last_saved_at: Option<i64>,
+    last_error: Option<PersistFailure>, // 마지막 저장 실패 (이후 저장에 성공하면 지워짐)
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: Option<AppData>, // 가장 최근 상태만 남김
+    due: Option<Instant>,     // pending이 있을 때 저장할 시각
+    writing: bool,
+    started: u64, // 지금까지 시작한 저장 횟수 (flush가 자기가 기다린 저장이 있었는지 구분)
+    paused: usize,
+    last_saved_at: Option<i64>,
+    last_error: Option<PersistFailure>,
+}
+
+/// 백그라운드 저장 작업자. 명령은 schedule로 최신 상태만 넘기고 바로 반환하며,
+/// 작업자 스레드가 DEBOUNCE 동안 모인 변경을 한 번에 암호화해 쓴다.
+#[derive(Default)]
+pub struct Persister {
+    queue: Mutex<Queue>,
+    changed: Condvar,
+}
+
+/// 살아 있는 동안 저장을 멈춘다 (Persister::pause). 멈춘 사이의 변경은 가드가 사라진 뒤 저장된다.
+pub struct PauseGuard<'a>(&'a Persister);
+
+impl Drop for PauseGuard<'_> {
+    fn drop(&mut self) {
+        self.0.queue.lock().unwrap().paused -= 1;
+        self.0.changed.notify_all();
+    }
+}
+
+impl Persister {
+    pub fn schedule(&self, data: AppData) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.pending = Some(data);
+        // 기한은 첫 변경 기준이므로 변경이 계속 들어와도 저장이 무한히 밀리지 않음
+        queue.due.get_or_insert_with(|| Instant::now() + DEBOUNCE);
+        self.changed.notify_all();
+    }
+
+    /// 대기 중인 변경을 바로 쓰고 끝날 때까지 기다린다 (창 닫기·종료, 다른 파일보다 먼저 저장되어야 할 때).
+    /// 다른 작업이 저장을 멈춰 두었으면 풀릴 때까지 기다렸다가 쓴다. 기다린 저장이 실패했으면 그 에러를 돌려준다
+    /// (쓸 것이 없었으면 이전 실패와 관계없이 성공).
+    pub fn flush(&self) -> AppResult<()> {
+        self.drain(false)
+    }
+
+    /// 대기 중인 변경을 쓴 뒤 가드가 살아 있는 동안 저장을 멈춘다.
+    /// 키 교체·잠금·다시 읽기처럼 그사이 저장이 끼어들면 안 되는 작업을 감싼다.
+    /// 다른 가드가 있으면 풀릴 때까지 기다리므로 한 스레드에서 겹쳐 잡으면 안 된다.
+    pub fn pause(&self) -> AppResult<PauseGuard<'_>> {
+        self.drain(true)?;
+        Ok(PauseGuard(self))
+    }
+
+    fn drain(&self, pause: bool) -> AppResult<()> {
+        let mut queue = self.queue.lock().unwrap();
+        let started = queue.started;
+        let writing = queue.writing;
+        if queue.pending.is_some() {
+            queue.due = Some(Instant::now());
+            self.changed.notify_all();
+        }
+        // 멈춘 동안에는 대기 중인 변경이 써지지 않으므로 멈춤이 풀리고 다 쓸 때까지 기다림
+        // (pause는 다른 작업의 멈춤과 겹치지 않도록 멈춤이 없을 때까지 기다림)
+        while queue.writing || queue.pending.is_some() || (pause && queue.paused > 0) {
+            queue = self.changed.wait(queue).unwrap();
+        }
+        // last_error는 가장 최근 저장의 결과이므로, 기다리는 동안 끝난 저장이 있을 때만 돌려줌
+        // (이전에 실패한 뒤 쓸 것이 없었다면 그 에러는 이번 호출과 무관)
+        let waited = writing || queue.started > started;
+        if let Some(failure) = queue.last_error.as_ref().filter(|_| waited) {
+            return Err(failure.error.clone());
+        }
+        if pause {
+            queue.paused += 1;
+        }
+        Ok(())
+    }
+
+    pub fn status(&self) -> PersistStatus {
+        let queue = self.queue.lock().unwrap();
+        PersistStatus {
+            pending: queue.pending.is_some() || queue.writing,
+            last_saved_at: queue.last_saved_at,
+            last_error: queue.last_error.clone(),
+        }
+    }
+
+    // 저장할 때가 된 상태를 꺼낸다 (그때까지 대기)
+    fn next(&self) -> AppData {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            match queue.due {
+                Some(due) if queue.paused == 0 => {
+                    let now = Instant::now();
+                    if now < due {
+                        queue = self.changed.wait_timeout(queue, due - now).unwrap().0;
+                        continue;
+                    }
+                    queue.due = None;
+                    if let Some(data) = queue.pending.take() {
+                        queue.writing = true;
+                        queue.started += 1;
+                        return data;
+                    }
+                }
+                _ => queue = self.changed.wait(queue).unwrap(),
+            }
+        }
+    }
+
+    fn finish(&self, app: &tauri::AppHandle, result: AppResult<()>) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.writing = false;
+        let newly_failed = match result {
+            Ok(()) => {
+                queue.last_saved_at = Some(now_secs());
+                queue.last_error = None;
+                None
+            }
+            Err(error) => {
+                let failure = PersistFailure { error, at: now_secs() };
+                // 실패가 이어지는 동안에는 처음 한 번만 알림
+                let first = queue.last_error.is_none();
+                queue.last_error = Some(failure.clone());
+                first.then_some(failure)
+            }
+        };
+        drop(queue);
+        self.changed.notify_all();
+
+        if let Some(failure) = newly_failed {
+            if let Err(e) = app.emit_all(PERSIST_FAILED_EVENT, failure) {
+                eprintln!("persist failure emit failed: {e}");
+            }
+        }
+    }
+}
+
+fn write(app: &tauri::AppHandle, data: &AppData) -> AppResult<()> {
+    storage::save_encrypted(app, data, data.compression)?;
+    storage::snapshot_if_due(app, data, now_secs())
+}
+
+/// 저장 작업자 스레드를 시작한다. Persister는 미리 manage되어 있어야 한다.
+pub fn start(app: tauri::AppHandle) {
+    thread::spawn(move || {
+        let persister = app.state::<Persister>();
+        loop {
+            let data = persister.next();
+            let result = write(&app, &data);
+            persister.finish(&app, result);
+        }
+    });
+}
@@ -0,0 +1,79 @@
+use crate::error::{AppError, AppResult, ErrorCode};
+use crate::{now_secs, storage};
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::Manager;
+
+const LOAD_FAILED_EVENT: &str = "data-load-failed";
+/// 복구 모드에서 데이터에 접근하려 할 때의 에러 (프론트엔드가 복구 화면을 보여줌)
+pub const RECOVERY_MODE: &str = "recovery mode: stored data could not be loaded";
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    WrongKey,       // 키를 찾을 수 없거나 다른(교체된) 키로 암호화됨
+    Tampered,       // 인증 태그 불일치 (변조 또는 키 불일치)
+    Corrupted,      // 잘린 파일, 깨진 JSON
+    UnknownVersion, // 더 새로운 앱이 저장한 형식
+    Other,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LoadFailure {
+    kind: FailureKind,
+    message: String,
+    at: i64,
+    quarantined: Vec<String>, // quarantine/ 안의 파일 이름
+}
+
+/// 저장된 데이터를 읽지 못해 복구 모드인 동안의 실패 정보.
+/// 이 상태에서는 빈 데이터가 기존 파일을 덮어쓰지 않도록 모든 데이터 명령이 RECOVERY_MODE 에러를 반환한다.
+#[derive(Default)]
+pub struct RecoveryState(Mutex<Option<LoadFailure>>);
+
+impl RecoveryState {
+    pub fn failure(&self) -> Option<LoadFailure> {
+        self.0.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        *self.0.lock().unwrap() = None;
+    }
+}
+
+// 에러 코드로 원인을 분류
+fn classify(code: ErrorCode) -> FailureKind {
+    match code {
+        ErrorCode::UnsupportedVersion => FailureKind::UnknownVersion,
+        ErrorCode::KeyNotFound | ErrorCode::WrongKey | ErrorCode::Keyring => FailureKind::WrongKey,
+        ErrorCode::Tampered | ErrorCode::WrongPassphrase => FailureKind::Tampered,
+        ErrorCode::Corrupted => FailureKind::Corrupted,
+        _ => FailureKind::Other,
+    }
+}
+
+/// 로드 실패 처리: 읽을 수 없는 파일을 격리하고 복구 모드로 들어간 뒤 이벤트로 알린다.
+/// 시작 직후에는 프론트엔드가 아직 이벤트를 듣지 않을 수 있으므로 get_recovery_status로도 조회 가능.
+pub fn enter(app: &tauri::AppHandle, error: AppError) {
+    eprintln!("entering recovery mode: {error}");
+    let now = now_secs();
+    let quarantined = storage::quarantine_data_file(app, now).unwrap_or_else(|e| {
+        eprintln!("quarantine failed: {e}");
+        Vec::new()
+    });
+    let failure = LoadFailure {
+        kind: classify(error.code),
+        message: error.to_string(),
+        at: now,
+        quarantined,
+    };
+    *app.state::<RecoveryState>().0.lock().unwrap() = Some(failure.clone());
+    if let Err(e) = app.emit_all(LOAD_FAILED_EVENT, failure) {
+        eprintln!("load failure emit failed: {e}");
+    }
+}
+
+/// 격리한 파일을 되돌려 다시 읽을 수 있게 한다 (키 정책을 고치거나 키체인이 풀린 뒤 다시 시도).
+pub fn restore_quarantined(app: &tauri::AppHandle, failure: &LoadFailure) -> AppResult<()> {
+    storage::restore_quarantined(app, &failure.quarantined)
+}
@@ -0,0 +1,89 @@
+use crate::{now_secs, persist, AppData, AppState};
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+use tauri::Manager;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const REMINDER_EVENT: &str = "task-reminder";
+
+#[derive(Clone, Serialize)]
+pub struct ReminderEvent {
+    id: u64,
+    text: String,
+    due_at: i64,
+    offset_secs: i64, // 마감 몇 초 전 알림인지 (0 = 마감 시각)
+}
+
+/// 마감 알림 스케줄러 스레드를 시작한다.
+/// 알림 발송 기록(reminders_fired)은 AppData에 함께 저장되므로 재시작해도 중복 알림이 없고,
+/// 앱이 꺼져 있는 동안 지나간 알림은 다음 확인 때 한 번 보낸다.
+pub fn start(app: tauri::AppHandle) {
+    thread::spawn(move || loop {
+        check_reminders(&app);
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+fn check_reminders(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let mut data = state.0.lock().unwrap();
+    let due = collect_due_reminders(&mut data, now_secs());
+    if due.is_empty() {
+        return;
+    }
+    let snapshot = data.clone();
+    drop(data);
+    persist(app, snapshot);
+
+    for reminder in due {
+        if let Err(e) = app.emit_all(REMINDER_EVENT, reminder.clone()) {
+            eprintln!("reminder emit failed: {e}");
+        }
+        show_notification(app, &reminder);
+    }
+}
+
+// 알림 시각이 지난 미완료 할 일을 찾아 발송 처리로 표시하고 반환
+fn collect_due_reminders(data: &mut AppData, now: i64) -> Vec<ReminderEvent> {
+    let mut due = Vec::new();
+    for task in data.tasks.iter_mut().filter(|t| !t.completed) {
+        let Some(due_at) = task.due_at else {
+            continue;
+        };
+        let offsets = if task.reminder_offsets.is_empty() {
+            vec![0]
+        } else {
+            task.reminder_offsets.clone()
+        };
+        for offset in offsets {
+            if due_at - offset > now || task.reminders_fired.contains(&offset) {
+                continue;
+            }
+            task.reminders_fired.push(offset);
+            due.push(ReminderEvent {
+                id: task.id,
+                text: task.text.clone(),
+                due_at,
+                offset_secs: offset,
+            });
+        }
+    }
+    due
+}
+
+fn show_notification(app: &tauri::AppHandle, reminder: &ReminderEvent) {
+    let body = if reminder.offset_secs == 0 {
+        "마감 시간이 되었습니다.".to_string()
+    } else {
+        format!("마감까지 {}분 남았습니다.", reminder.offset_secs / 60)
+    };
+    // 데스크톱 알림을 지원하지 않는 환경에서는 이벤트만 전달
+    let result = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+        .title(&reminder.text)
+        .body(body)
+        .show();
+    if let Err(e) = result {
+        eprintln!("notification failed: {e}");
+    }
+}
@@ -0,0 +1,275 @@
+use crate::error::{AppError, AppResult};
+use crate::TodoItem;
+use serde::Serialize;
+use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+
+// 한글 음절의 초성 (호환용 자모, 초성 검색용)
+const CHOSEONG: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ', 'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+#[derive(Clone, Serialize)]
+pub struct Highlight {
+    start: usize, // UTF-16 오프셋 (JS 문자열 인덱스와 동일)
+    end: usize,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SearchHit {
+    task: TodoItem,
+    score: u32,
+    highlights: Vec<Highlight>,
+}
+
+#[derive(Default)]
+struct Query {
+    terms: Vec<String>, // 정규화된 검색어 (따옴표 구절은 하나의 항목)
+    done: Option<bool>,
+    created_on: Option<(i64, i64)>, // [시작, 끝) 초
+    before: Option<i64>,
+    after: Option<i64>,
+}
+
+/// 검색 대상 텍스트를 정규화한 결과.
+/// 정규화 문자마다 원문 문자 인덱스를 기록해 두어 하이라이트를 원문 위치로 되돌린다.
+struct Normalized {
+    chars: Vec<char>,
+    origin: Vec<usize>,
+}
+
+// 소문자화 + 분음 부호 제거. 한글 음절은 NFD로 자모까지 분해되므로
+// 입력 중인 글자("하")로 완성된 글자("한")도 찾을 수 있다.
+fn normalize(text: &str) -> Normalized {
+    let mut chars = Vec::new();
+    let mut origin = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        let mut decomposed = Vec::new();
+        decompose_canonical(c, |d| decomposed.push(d));
+        for d in decomposed.into_iter().filter(|d| !is_combining_mark(*d)) {
+            for l in d.to_lowercase() {
+                chars.push(l);
+                origin.push(i);
+            }
+        }
+    }
+    Normalized { chars, origin }
+}
+
+fn normalize_term(term: &str) -> String {
+    normalize(term).chars.into_iter().collect()
+}
+
+fn is_choseong_only(term: &str) -> bool {
+    !term.is_empty() && term.chars().all(|c| CHOSEONG.contains(&c))
+}
+
+// 한글 음절은 초성으로, 그 외 문자는 그대로 둔 문자열 (원문과 문자 단위로 1:1 대응)
+fn choseong_of(text: &str) -> Vec<char> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0xAC00..=0xD7A3 => CHOSEONG[((c as u32 - 0xAC00) / 588) as usize],
+            _ => c,
+        })
+        .collect()
+}
+
+fn parse_date(value: &str) -> AppResult<i64> {
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc().timestamp())
+        .ok_or_else(|| AppError::invalid(format!("invalid date (expected YYYY-MM-DD): {value}")))
+}
+
+// 공백으로 나누되 따옴표로 묶인 구절은 하나의 토큰으로 취급
+fn tokenize(input: &str) -> Vec<(String, bool)> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quote = false;
+    for c in input.chars() {
+        match c {
+            '"' => {
+                if in_quote || !current.is_empty() {
+                    tokens.push((std::mem::take(&mut current), in_quote));
+                }
+                in_quote = !in_quote;
+            }
+            c if c.is_whitespace() && !in_quote => {
+                if !current.is_empty() {
+                    tokens.push((std::mem::take(&mut current), false));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push((current, in_quote));
+    }
+    tokens.retain(|(t, _)| !t.trim().is_empty());
+    tokens
+}
+
+fn parse_query(input: &str) -> AppResult<Query> {
+    let mut query = Query::default();
+    for (token, quoted) in tokenize(input) {
+        if !quoted {
+            if let Some((op, value)) = token.split_once(':') {
+                match op.to_lowercase().as_str() {
+                    "is" => {
+                        query.done = match value.to_lowercase().as_str() {
+                            "done" => Some(true),
+                            "open" => Some(false),
+                            _ => return Err(AppError::invalid(format!("unknown is: value: {value}"))),
+                        };
+                        continue;
+                    }
+                    "created" => {
+                        let start = parse_date(value)?;
+                        query.created_on = Some((start, start + 86400));
+                        continue;
+                    }
+                    "before" => {
+                        query.before = Some(parse_date(value)?);
+                        continue;
+                    }
+                    "after" => {
+                        query.after = Some(parse_date(value)? + 86400);
+                        continue;
+                    }
+                    // 알 수 없는 연산자는 일반 검색어로 취급 (예: "10:30")
+                    _ => {}
+                }
+            }
+        }
+        query.terms.push(token);
+    }
+    Ok(query)
+}
+
+fn matches_filters(task: &TodoItem, query: &Query) -> bool {
+    if query.done.is_some_and(|done| task.completed != done) {
+        return false;
+    }
+    if let Some((start, end)) = query.created_on {
+        if task.created_at < start || task.created_at >= end {
+            return false;
+        }
+    }
+    if query.before.is_some_and(|ts| task.created_at >= ts) {
+        return false;
+    }
+    if query.after.is_some_and(|ts| task.created_at < ts) {
+        return false;
+    }
+    true
+}
+
+fn find_all(haystack: &[char], needle: &[char]) -> Vec<usize> {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return Vec::new();
+    }
+    (0..=haystack.len() - needle.len())
+        .filter(|&i| haystack[i..i + needle.len()] == *needle)
+        .collect()
+}
+
+// 원문 문자 인덱스 범위를 UTF-16 오프셋으로 변환
+fn to_utf16_range(text: &str, start: usize, end: usize) -> Highlight {
+    let offset = |n: usize| text.chars().take(n).map(char::len_utf16).sum();
+    Highlight {
+        start: offset(start),
+        end: offset(end),
+    }
+}
+
+// 모든 검색어가 포함되어야 일치. 일치 위치가 단어 시작이거나 전체 일치면 가산점
+fn score_task(task: &TodoItem, terms: &[String]) -> Option<(u32, Vec<Highlight>)> {
+    let text = normalize(&task.text);
+    let initials = choseong_of(&task.text);
+    let mut score = 0;
+    let mut highlights = Vec::new();
+
+    for term in terms {
+        let (positions, len, original) = if is_choseong_only(term) {
+            let needle: Vec<char> = term.chars().collect();
+            (find_all(&initials, &needle), needle.len(), true)
+        } else {
+            let needle: Vec<char> = normalize_term(term).chars().collect();
+            (find_all(&text.chars, &needle), needle.len(), false)
+        };
+        if positions.is_empty() {
+            return None;
+        }
+
+        score += 10;
+        for pos in &positions {
+            // 원문 기준 (시작, 끝) 문자 인덱스
+            let (start, end) = if original {
+                (*pos, pos + len)
+            } else {
+                (text.origin[*pos], text.origin[pos + len - 1] + 1)
+            };
+            let at_word_start = start == 0
+                || task
+                    .text
+                    .chars()
+                    .nth(start - 1)
+                    .is_some_and(|c| c.is_whitespace() || c.is_ascii_punctuation());
+            if at_word_start {
+                score += 5;
+            }
+            highlights.push(to_utf16_range(&task.text, start, end));
+        }
+        score += positions.len().min(5) as u32;
+        // 초성 검색어는 초성 열, 그 외는 정규화된 본문 길이와 비교
+        let whole = if original { initials.len() } else { text.chars.len() };
+        if len == whole {
+            score += 20;
+        }
+    }
+
+    highlights.sort_by_key(|h| h.start);
+    Some((score, highlights))
+}
+
+/// 검색어로 할 일을 찾아 점수 순으로 반환한다.
+/// 지원 연산자: is:done, is:open, created:YYYY-MM-DD, before:YYYY-MM-DD, after:YYYY-MM-DD
+pub fn search<'a>(
+    tasks: impl Iterator<Item = &'a TodoItem>,
+    input: &str,
+    limit: usize,
+) -> AppResult<Vec<SearchHit>> {
+    let query = parse_query(input)?;
+    let mut hits: Vec<SearchHit> = tasks
+        .filter(|t| matches_filters(t, &query))
+        .filter_map(|t| {
+            score_task(t, &query.terms).map(|(score, highlights)| SearchHit {
+                task: t.clone(),
+                score,
+                highlights,
+            })
+        })
+        .collect();
+
+    // 점수가 같으면 최근에 만든 할 일 우선
+    hits.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(b.task.created_at.cmp(&a.task.created_at))
+    });
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choseong_exact_match_gets_bonus() {
+        let terms = vec!["ㅎㄱ".to_string()];
+        let (exact, _) = score_task(&TodoItem::for_test(1, "한글", 0), &terms).unwrap();
+        let (partial, _) = score_task(&TodoItem::for_test(2, "한글 공부", 0), &terms).unwrap();
+        assert_eq!(exact, partial + 20);
+    }
+}
@@ -1,24 +1,268 @@
-use aes_gcm::aead::{Aead, KeyInit};
+use crate::error::{AppError, AppResult, Context, ErrorCode};
+use crate::stream::{StreamReader, StreamWriter, DEFAULT_CHUNK_SIZE, NONCE_PREFIX_LEN};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
 use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
 use base64::Engine;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use hkdf::Hkdf;
 use hmac::Hmac;
 use rand::RngCore;
-use sha2::Sha256;
-use std::fs;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 type HmacSha256 = Hmac<Sha256>;
 
 const DATA_FILENAME: &str = "app_data.enc.json";
+const ARCHIVE_FILENAME: &str = "archive.enc.json";
+const COMPRESSION_DEFLATE: &str = "deflate";
+const COMPRESSION_ZSTD: &str = "zstd";
+const ZSTD_LEVEL: i32 = 3;
+const CIPHER_AES_256_GCM: &str = "aes-256-gcm";
+// envelope 버전: 1 = 기기 키(로컬 파일·백업), 2 = 비밀번호 백업, 3 = 암호 방식·키 식별자·AAD를 기록하는 통합 형식.
+// 여기까지는 base64 JSON envelope이고 4부터는 청크 단위로 암호화한 바이너리 스트림 컨테이너.
+// 5부터는 청크 암호화에 키를 그대로 쓰지 않고 HKDF로 용도별 하위 키를 유도한다
+// (v1·v2는 같은 키로 HMAC과 AES-GCM을 함께 썼음).
+// 읽기는 모든 버전을 지원하고 저장할 때는 항상 현재 버전(STREAM_VERSION)으로 쓴다
+const ENVELOPE_VERSION: u32 = 3;
+const FIRST_STREAM_VERSION: u32 = 4;
+const STREAM_VERSION: u32 = 5;
+const STREAM_KEY_LABEL: &[u8] = b"todo-app/stream/aes-256-gcm";
+const CIPHER_AES_256_GCM_STREAM: &str = "aes-256-gcm-stream";
+// 스트림 컨테이너: 매직 + u32 LE 헤더 길이 + 헤더 JSON + 청크들 (JSON envelope는 '{'로 시작하므로 구분됨)
+const STREAM_MAGIC: &[u8; 8] = b"TODOENC\0";
+const MAX_HEADER_LEN: usize = 64 * 1024;
+const KDF_ARGON2ID: &str = "argon2id";
+const MIN_PASSPHRASE_LEN: usize = 8;
+/// 비밀번호로 보호된 백업을 비밀번호 없이 가져오려 할 때의 에러 (프론트엔드가 비밀번호를 물어봄)
+pub const PASSPHRASE_REQUIRED: &str = "passphrase required";
+const SNAPSHOT_DIR: &str = "snapshots";
+const QUARANTINE_DIR: &str = "quarantine";
+const SNAPSHOT_PREFIX: &str = "snapshot_";
+const SNAPSHOT_SUFFIX: &str = ".enc.json";
+
+// 스냅샷 종류별 (이름, 생성 간격(초), 보관 개수)
+const SNAPSHOT_SCHEDULES: [(&str, i64, usize); 2] = [("hourly", 3600, 24), ("daily", 86400, 7)];
+
+#[derive(Clone, serde::Serialize)]
+pub struct SnapshotInfo {
+    id: String, // 파일 이름 (restore/delete에 그대로 전달)
+    kind: String,
+    created_at: i64,
+    size_bytes: u64,
+}
 const KEY_FILENAME: &str = "key_fallback.b64";
 const KEYRING_USERNAME: &str = "data_key_v1";
+const WRAPPED_KEY_FILENAME: &str = "key_wrapped.json";
+const KEY_POLICY_FILENAME: &str = "key_policy.json";
+const RETIRED_KEYS_FILENAME: &str = "retired_keys.json";
+// 키 교체 중 새 키로 다시 암호화해 둔 파일 (새 키 저장이 끝나면 원본과 교체)
+const ROTATING_SUFFIX: &str = ".rotating";
+/// 잠금 모드에서 unlock 전에 데이터에 접근하려 할 때의 에러 (프론트엔드가 비밀번호를 물어봄)
+pub const LOCKED: &str = "locked";
+
+// 잠금 모드에서 unlock으로 풀어 둔 데이터 키 (프로세스 메모리에만 보관)
+static UNLOCKED_KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+// 비밀번호에서 유도한 키로 감싼 데이터 키. 잠금 모드에서는 이 파일만 남고
+// 평문 키(fallback 파일·키체인)는 지운다
+#[derive(serde::Serialize, serde::Deserialize)]
+struct WrappedKey {
+    kdf: KdfParams,
+    nonce_b64: String,
+    ct_b64: String,
+}
+
+/// 데이터 키를 어디에 보관할지 (key_policy.json에 평문으로 저장, 키 자체는 아님)
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    KeyringOnly,
+    FileOnly,
+    #[default]
+    KeyringWithFileFallback,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct KeyPolicy {
+    source: KeySource,
+}
+
+// 현재 키를 실제로 읽어 온 곳
+#[derive(Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActiveKeySource {
+    Keyring,
+    File,
+    Password, // 잠금 모드 (비밀번호로 감싼 키)
+    None,     // 아직 키가 없거나 정책상 위치에서 찾지 못함
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct KeyStatus {
+    policy: KeySource,
+    active_source: ActiveKeySource,
+    key_id: Option<String>,
+    keyring_has_key: bool,
+    file_has_key: bool,
+    keyring_error: Option<String>, // 키체인 접근이 실패한 이유
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct LockStatus {
+    enabled: bool,
+    locked: bool,
+}
 
 #[derive(serde::Serialize, serde::Deserialize)]
 struct Envelope {
     v: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cipher: Option<String>, // v3: 암호 방식 (v1/v2는 항상 AES-256-GCM)
     nonce_b64: String,
     ct_b64: String,
     hmac_b64: Option<String>, // 백업 파일용 (로컬 저장에는 없을 수 있음)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compression: Option<String>, // 암호화 전에 적용한 압축 방식 (없으면 비압축)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfParams>, // v2 백업: 기기 키 대신 비밀번호에서 키를 유도
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_id: Option<String>, // 기기 키로 암호화한 경우 키 식별자 (교체된 키를 구분)
+}
+
+/// 암호화 전에 평문에 적용하는 압축. 헤더에 기록되므로 읽을 때는 설정과 관계없이 자동으로 풀린다.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    None,
+    Deflate,
+    #[default]
+    Zstd,
+}
+
+impl Compression {
+    fn name(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Deflate => Some(COMPRESSION_DEFLATE),
+            Compression::Zstd => Some(COMPRESSION_ZSTD),
+        }
+    }
+
+    fn from_name(name: Option<&str>) -> AppResult<Self> {
+        match name {
+            None => Ok(Compression::None),
+            Some(COMPRESSION_DEFLATE) => Ok(Compression::Deflate),
+            Some(COMPRESSION_ZSTD) => Ok(Compression::Zstd),
+            Some(other) => Err(AppError::new(ErrorCode::UnsupportedVersion, format!("unsupported compression: {other}"))),
+        }
+    }
+}
+
+// 스트림 컨테이너 헤더. 헤더 JSON 바이트 전체를 각 청크의 AAD에 묶어 변조를 막는다
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StreamHeader {
+    v: u32,
+    cipher: String,
+    chunk_size: u32,
+    nonce_prefix_b64: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compression: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfParams>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_id: Option<String>,
+}
+
+// 열어 둔 암호화 파일. 구버전 JSON envelope는 통째로 읽고, 스트림 컨테이너는 본문을 청크 단위로 읽는다
+enum Sealed {
+    Envelope(Envelope),
+    Stream {
+        header: StreamHeader,
+        header_json: Vec<u8>,
+        body: BufReader<File>,
+    },
+}
+
+impl Sealed {
+    fn key_id(&self) -> Option<&str> {
+        match self {
+            Sealed::Envelope(env) => env.key_id.as_deref(),
+            Sealed::Stream { header, .. } => header.key_id.as_deref(),
+        }
+    }
+
+    fn compression(&self) -> AppResult<Compression> {
+        match self {
+            Sealed::Envelope(env) => Compression::from_name(env.compression.as_deref()),
+            Sealed::Stream { header, .. } => Compression::from_name(header.compression.as_deref()),
+        }
+    }
+}
+
+// 교체되어 더는 쓰지 않는 키 (키 자체는 남기지 않고 식별자만 기록)
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RetiredKey {
+    key_id: String,
+    retired_at: i64,
+}
+
+// 비밀번호 기반 키 유도 설정 (다른 기기에서 같은 키를 다시 만들 수 있도록 envelope에 저장)
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct KdfParams {
+    alg: String,
+    salt_b64: String,
+    m_cost: u32, // KiB
+    t_cost: u32,
+    p_cost: u32,
+}
+
+impl KdfParams {
+    fn generate() -> Self {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        Self {
+            alg: KDF_ARGON2ID.to_string(),
+            salt_b64: base64::engine::general_purpose::STANDARD.encode(salt),
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+
+    fn derive_key(&self, passphrase: &str) -> AppResult<[u8; 32]> {
+        if self.alg != KDF_ARGON2ID {
+            return Err(AppError::new(ErrorCode::UnsupportedVersion, format!("unsupported kdf: {}", self.alg)));
+        }
+        let salt = base64::engine::general_purpose::STANDARD
+            .decode(self.salt_b64.as_bytes())
+            .context("salt decode error")?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .context("invalid kdf params")?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .context("key derivation error")?;
+        Ok(key)
+    }
+}
+
+fn decompress(compression: Option<&str>, data: Vec<u8>) -> AppResult<Vec<u8>> {
+    match compression {
+        None => Ok(data),
+        Some(COMPRESSION_DEFLATE) => {
+            let mut out = Vec::new();
+            DeflateDecoder::new(data.as_slice())
+                .read_to_end(&mut out)
+                .context_as(ErrorCode::Corrupted, "decompress error")?;
+            Ok(out)
+        }
+        Some(other) => Err(AppError::new(ErrorCode::UnsupportedVersion, format!("unsupported compression: {other}"))),
+    }
 }
 
 fn service_name(app: &tauri::AppHandle) -> String {
@@ -31,254 +275,1142 @@ fn service_name(app: &tauri::AppHandle) -> String {
     }
 }
 
-fn app_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+fn app_data_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
     app.path_resolver()
         .app_data_dir()
-        .ok_or_else(|| "failed to resolve app data dir".to_string())
+        .ok_or_else(|| AppError::new(ErrorCode::Io, "failed to resolve app data dir"))
 }
 
-fn ensure_parent_dir(path: &Path) -> Result<(), String> {
+fn ensure_parent_dir(path: &Path) -> AppResult<()> {
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("failed to create data dir: {e}"))?;
+        fs::create_dir_all(parent).context("failed to create data dir")?;
     }
     Ok(())
 }
 
-fn get_key_from_keyring(app: &tauri::AppHandle) -> Option<[u8; 32]> {
-    let service = service_name(app);
-    let entry = match keyring::Entry::new(&service, KEYRING_USERNAME) {
-        Ok(e) => e,
-        Err(_) => return None, // 키체인 접근 실패 시 조용히 None 반환
-    };
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+// 직전 세대 파일 (현재 파일이 손상되면 이 파일을 읽음)
+fn previous_path(path: &Path) -> PathBuf {
+    sibling_path(path, ".prev")
+}
+
+/// 임시 파일에 쓰고 fsync 후 rename으로 교체한다. 쓰는 도중 중단되어도 기존 파일은 온전하다.
+/// keep_previous면 교체 전 파일이 올바른 envelope일 때만 `.prev`로 남겨 둔다.
+fn write_atomic(path: &Path, bytes: &[u8], keep_previous: bool) -> AppResult<()> {
+    write_atomic_with(path, keep_previous, |mut file| {
+        file.write_all(bytes).context("temp file write error")?;
+        Ok(file)
+    })
+}
+
+/// write_atomic과 같지만 내용을 임시 파일에 직접 써 넣는다 (큰 파일을 메모리에 모으지 않도록).
+fn write_atomic_with(
+    path: &Path,
+    keep_previous: bool,
+    write: impl FnOnce(File) -> AppResult<File>,
+) -> AppResult<()> {
+    ensure_parent_dir(path)?;
+    let tmp = sibling_path(path, ".tmp");
+    let written = File::create(&tmp)
+        .context("temp file create error")
+        .and_then(write)
+        .and_then(|file| file.sync_all().context("temp file sync error"));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+
+    // 손상된 파일로 정상 세대를 덮어쓰지 않도록 암호화 파일 형식인지 확인 후 보관
+    if keep_previous && open_sealed(path).is_ok() {
+        fs::rename(path, previous_path(path)).context("previous generation rename error")?;
+    }
+    fs::rename(&tmp, path).context("file replace error")?;
 
+    // rename 자체가 디스크에 반영되도록 디렉터리도 동기화 (Windows에서는 지원되지 않으므로 무시)
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+// 매직 바이트로 형식을 구분해 헤더만 읽는다 (스트림 본문은 read_sealed에서 청크 단위로 읽음)
+fn open_sealed(path: &Path) -> AppResult<Sealed> {
+    let mut file = BufReader::new(File::open(path).context("data read error")?);
+    if !file.fill_buf().context("data read error")?.starts_with(STREAM_MAGIC) {
+        let mut raw = String::new();
+        file.read_to_string(&mut raw).context("data read error")?;
+        let env = serde_json::from_str(&raw).context("envelope parse error")?;
+        return Ok(Sealed::Envelope(env));
+    }
+    file.consume(STREAM_MAGIC.len());
+    let mut len = [0u8; 4];
+    file.read_exact(&mut len).context("stream header read error")?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_HEADER_LEN {
+        return Err(AppError::new(ErrorCode::Corrupted, format!("invalid stream header length: {len}")));
+    }
+    let mut header_json = vec![0u8; len];
+    file.read_exact(&mut header_json).context("stream header read error")?;
+    let header = serde_json::from_slice(&header_json).context("stream header parse error")?;
+    Ok(Sealed::Stream {
+        header,
+        header_json,
+        body: file,
+    })
+}
+
+fn get_key_from_keyring(app: &tauri::AppHandle) -> AppResult<Option<[u8; 32]>> {
+    let service = service_name(app);
+    let entry = keyring::Entry::new(&service, KEYRING_USERNAME).context("keyring unavailable")?;
     match entry.get_password() {
         Ok(b64) => {
             let engine = base64::engine::general_purpose::STANDARD;
-            let decoded = match engine.decode(b64.as_bytes()) {
-                Ok(d) => d,
-                Err(_) => return None,
-            };
+            let decoded = engine
+                .decode(b64.as_bytes())
+                .context("keyring key decode error")?;
             if decoded.len() != 32 {
-                return None;
+                return Err(AppError::new(ErrorCode::Corrupted, "keyring key has invalid length"));
             }
             let mut key = [0u8; 32];
             key.copy_from_slice(&decoded);
-            Some(key)
+            Ok(Some(key))
         }
-        Err(keyring::Error::NoEntry) => None,
-        Err(_) => None, // 다른 에러도 조용히 무시 (비밀번호 요구 등)
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e).context("keyring read error"),
     }
 }
 
-fn set_key_to_keyring(app: &tauri::AppHandle, key: &[u8; 32]) -> bool {
+fn set_key_to_keyring(app: &tauri::AppHandle, key: &[u8; 32]) -> AppResult<()> {
     let service = service_name(app);
-    let entry = match keyring::Entry::new(&service, KEYRING_USERNAME) {
-        Ok(e) => e,
-        Err(_) => return false, // 키체인 접근 실패 시 조용히 실패
-    };
+    let entry = keyring::Entry::new(&service, KEYRING_USERNAME).context("keyring unavailable")?;
     let engine = base64::engine::general_purpose::STANDARD;
-    let b64 = engine.encode(key);
-    entry.set_password(&b64).is_ok() // 성공 여부만 반환
+    entry
+        .set_password(&engine.encode(key))
+        .context("keyring write error")
 }
 
-fn get_key_from_fallback_file(app: &tauri::AppHandle) -> Result<Option<[u8; 32]>, String> {
-    let dir = app_data_dir(app)?;
-    let path = dir.join(KEY_FILENAME);
+fn fallback_key_path(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    Ok(app_data_dir(app)?.join(KEY_FILENAME))
+}
+
+fn get_key_from_fallback_file(app: &tauri::AppHandle) -> AppResult<Option<[u8; 32]>> {
+    let path = fallback_key_path(app)?;
     if !path.exists() {
         return Ok(None);
     }
-    let b64 = fs::read_to_string(&path).map_err(|e| format!("fallback key read error: {e}"))?;
+    let b64 = fs::read_to_string(&path).context("fallback key read error")?;
     let engine = base64::engine::general_purpose::STANDARD;
     let decoded = engine
         .decode(b64.trim().as_bytes())
-        .map_err(|e| format!("fallback key decode error: {e}"))?;
+        .context("fallback key decode error")?;
     if decoded.len() != 32 {
-        return Err("fallback key has invalid length".to_string());
+        return Err(AppError::new(ErrorCode::Corrupted, "fallback key has invalid length"));
     }
     let mut key = [0u8; 32];
     key.copy_from_slice(&decoded);
     Ok(Some(key))
 }
 
-fn set_key_to_fallback_file(app: &tauri::AppHandle, key: &[u8; 32]) -> Result<(), String> {
-    let dir = app_data_dir(app)?;
-    let path = dir.join(KEY_FILENAME);
+fn set_key_to_fallback_file(app: &tauri::AppHandle, key: &[u8; 32]) -> AppResult<()> {
+    let path = fallback_key_path(app)?;
     ensure_parent_dir(&path)?;
     let engine = base64::engine::general_purpose::STANDARD;
     let b64 = engine.encode(key);
-    fs::write(&path, b64.as_bytes()).map_err(|e| format!("fallback key write error: {e}"))?;
+    write_atomic(&path, b64.as_bytes(), false).context("fallback key write error")?;
     Ok(())
 }
 
-fn get_or_create_key(app: &tauri::AppHandle) -> Result<[u8; 32], String> {
-    // 1) fallback 파일 우선 (키체인 비밀번호 요구 방지)
-    if let Some(key) = get_key_from_fallback_file(app)? {
-        return Ok(key);
+fn load_key_policy(app: &tauri::AppHandle) -> AppResult<KeySource> {
+    let path = app_data_dir(app)?.join(KEY_POLICY_FILENAME);
+    if !path.exists() {
+        return Ok(KeySource::default());
     }
+    let raw = fs::read_to_string(&path).context("key policy read error")?;
+    let policy: KeyPolicy = serde_json::from_str(&raw).context("key policy parse error")?;
+    Ok(policy.source)
+}
 
-    // 2) OS 키체인 시도 (실패해도 에러 없이 넘어감)
-    if let Some(key) = get_key_from_keyring(app) {
-        // 키체인에서 가져온 키를 fallback 파일에도 저장 (다음엔 바로 사용)
-        let _ = set_key_to_fallback_file(app, &key);
+fn save_key_policy(app: &tauri::AppHandle, source: KeySource) -> AppResult<()> {
+    let out = serde_json::to_string(&KeyPolicy { source }).context("key policy serialize error")?;
+    write_atomic(&app_data_dir(app)?.join(KEY_POLICY_FILENAME), out.as_bytes(), false)
+        .context("key policy write error")
+}
+
+// 정책에 따라 키를 읽는다. 어디서 읽었는지 함께 반환 (없으면 None)
+fn read_key(app: &tauri::AppHandle, policy: KeySource) -> AppResult<Option<([u8; 32], ActiveKeySource)>> {
+    match policy {
+        KeySource::KeyringOnly => Ok(get_key_from_keyring(app)?.map(|k| (k, ActiveKeySource::Keyring))),
+        KeySource::FileOnly => Ok(get_key_from_fallback_file(app)?.map(|k| (k, ActiveKeySource::File))),
+        KeySource::KeyringWithFileFallback => {
+            let keyring_error = match get_key_from_keyring(app) {
+                Ok(Some(key)) => return Ok(Some((key, ActiveKeySource::Keyring))),
+                Ok(None) => None,
+                Err(e) => Some(e),
+            };
+            let key = get_key_from_fallback_file(app)?;
+            if let (Some(_), Some(e)) = (&key, &keyring_error) {
+                eprintln!("using fallback key file ({e})");
+            }
+            Ok(key.map(|k| (k, ActiveKeySource::File)))
+        }
+    }
+}
+
+fn delete_key_from_keyring(app: &tauri::AppHandle) -> AppResult<()> {
+    let entry = keyring::Entry::new(&service_name(app), KEYRING_USERNAME)
+        .context("keyring unavailable")?;
+    match entry.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e).context("keyring delete error"),
+    }
+}
+
+// 정책에 따라 키를 저장한다. fallback 정책에서는 키체인 저장이 실패했거나
+// 이미 fallback 파일이 있으면 파일에도 써서 두 곳의 키가 어긋나지 않게 한다
+fn store_key(app: &tauri::AppHandle, key: &[u8; 32], policy: KeySource) -> AppResult<()> {
+    match policy {
+        KeySource::KeyringOnly => set_key_to_keyring(app, key),
+        KeySource::FileOnly => set_key_to_fallback_file(app, key),
+        KeySource::KeyringWithFileFallback => {
+            let keyring_result = set_key_to_keyring(app, key);
+            if let Err(e) = &keyring_result {
+                // 키체인에 다른 키가 남아 있으면 그쪽을 먼저 읽게 되므로 지우지 못하면 중단.
+                // 키체인에 접근할 수 없으면(서비스 없음 등) 남은 키도 읽히지 않으므로 파일에 저장
+                if let Err(del) = delete_key_from_keyring(app) {
+                    if get_key_from_keyring(app).is_ok_and(|stale| stale.is_some_and(|stale| stale != *key)) {
+                        return Err(AppError::new(ErrorCode::Keyring, format!("{e}; stale keyring key remains"))
+                            .with_details(del));
+                    }
+                }
+                eprintln!("storing key in fallback file ({e})");
+            }
+            if keyring_result.is_err() || fallback_key_path(app)?.exists() {
+                set_key_to_fallback_file(app, key)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn get_or_create_key(app: &tauri::AppHandle) -> AppResult<[u8; 32]> {
+    // 잠금 모드면 unlock으로 풀어 둔 키만 사용 (새 키를 만들면 기존 데이터를 못 읽게 됨)
+    if wrapped_key_path(app)?.exists() {
+        return UNLOCKED_KEY.lock().unwrap().ok_or_else(|| AppError::new(ErrorCode::Locked, LOCKED));
+    }
+
+    let policy = load_key_policy(app)?;
+    if let Some((key, _)) = read_key(app, policy)? {
         return Ok(key);
     }
 
-    // 3) 새 키 생성
+    // 저장된(또는 격리된) 데이터가 있는데 키가 없으면 새 키를 만들지 않음 (만들면 기존 데이터를 영영 못 읽음)
+    let data_path = app_data_dir(app)?.join(DATA_FILENAME);
+    if data_path.exists() || previous_path(&data_path).exists() || !list_quarantine(app)?.is_empty() {
+        return Err(AppError::new(ErrorCode::KeyNotFound, format!(
+            "data key not found under key policy {policy:?}; check get_key_status or change the key policy"
+        )));
+    }
+    create_key(app, policy)
+}
+
+fn create_key(app: &tauri::AppHandle, policy: KeySource) -> AppResult<[u8; 32]> {
     let mut key = [0u8; 32];
     rand::thread_rng().fill_bytes(&mut key);
-
-    // fallback 파일에 저장 (키체인은 시도만 하고 실패해도 무시)
-    set_key_to_fallback_file(app, &key)?;
-    let _ = set_key_to_keyring(app, &key); // 키체인 저장 시도 (실패해도 무시)
+    store_key(app, &key, policy)?;
     Ok(key)
 }
 
-pub fn load_encrypted(app: &tauri::AppHandle) -> Result<Option<Vec<u8>>, String> {
-    let dir = app_data_dir(app)?;
-    let path = dir.join(DATA_FILENAME);
-    if !path.exists() {
-        return Ok(None);
+/// 복구 모드에서 새로 시작할 때 사용. 키를 찾을 수 없으면 격리된 데이터가 있어도 새 키를 만든다
+/// (격리된 파일은 그대로 남으므로 나중에 원래 키를 찾으면 다시 읽을 수 있음).
+pub fn ensure_key_for_fresh_start(app: &tauri::AppHandle) -> AppResult<()> {
+    if wrapped_key_path(app)?.exists() {
+        return get_or_create_key(app).map(|_| ());
     }
-
-    let raw = fs::read_to_string(&path).map_err(|e| format!("data read error: {e}"))?;
-    let env: Envelope = serde_json::from_str(&raw).map_err(|e| format!("envelope parse error: {e}"))?;
-    if env.v != 1 {
-        return Err("unsupported data version".to_string());
+    let policy = load_key_policy(app)?;
+    if read_key(app, policy)?.is_none() {
+        create_key(app, policy)?;
     }
+    Ok(())
+}
 
-    let engine = base64::engine::general_purpose::STANDARD;
-    let nonce_bytes = engine
-        .decode(env.nonce_b64.as_bytes())
-        .map_err(|e| format!("nonce decode error: {e}"))?;
-    let ct = engine
-        .decode(env.ct_b64.as_bytes())
-        .map_err(|e| format!("ciphertext decode error: {e}"))?;
-    if nonce_bytes.len() != 12 {
-        return Err("invalid nonce length".to_string());
+pub fn key_status(app: &tauri::AppHandle) -> AppResult<KeyStatus> {
+    let policy = load_key_policy(app)?;
+    let file_has_key = get_key_from_fallback_file(app)?.is_some();
+    // 파일 전용 정책에서는 키체인을 건드리지 않음
+    let (keyring_has_key, keyring_error) = match policy {
+        KeySource::FileOnly => (false, None),
+        _ => match get_key_from_keyring(app) {
+            Ok(key) => (key.is_some(), None),
+            Err(e) => (false, Some(e.to_string())),
+        },
+    };
+
+    let (active_source, key) = if wrapped_key_path(app)?.exists() {
+        (ActiveKeySource::Password, *UNLOCKED_KEY.lock().unwrap())
+    } else {
+        match read_key(app, policy)? {
+            Some((key, source)) => (source, Some(key)),
+            None => (ActiveKeySource::None, None),
+        }
+    };
+    Ok(KeyStatus {
+        policy,
+        active_source,
+        key_id: key.as_ref().map(key_id),
+        keyring_has_key,
+        file_has_key,
+        keyring_error,
+    })
+}
+
+/// 키 보관 정책을 바꾼다. 현재 키를 새 정책의 위치에도 저장한 뒤 정책을 기록하므로
+/// 바꾼 직후에도 같은 키를 읽는다. 잠금 모드에서는 키가 비밀번호로 감싸져 있으므로 정책만 기록.
+pub fn set_key_policy(app: &tauri::AppHandle, source: KeySource) -> AppResult<()> {
+    if !wrapped_key_path(app)?.exists() {
+        let key = get_or_create_key(app)?;
+        store_key(app, &key, source)?;
     }
-    let nonce = Nonce::from_slice(&nonce_bytes);
+    save_key_policy(app, source)
+}
 
+/// 키를 OS 키체인으로 옮기고 평문 fallback 파일을 지운 뒤 정책을 키체인 전용으로 바꾼다.
+/// 키체인에서 같은 키를 다시 읽을 수 있는지 확인한 다음에만 파일을 지운다.
+pub fn migrate_key_to_keyring(app: &tauri::AppHandle) -> AppResult<()> {
+    if wrapped_key_path(app)?.exists() {
+        return Err(AppError::invalid_state("lock mode is enabled; the key is stored wrapped by the password"));
+    }
     let key = get_or_create_key(app)?;
-    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("cipher init error: {e}"))?;
-    let pt = cipher
-        .decrypt(nonce, ct.as_ref())
-        .map_err(|e| format!("decrypt failed (tampered or wrong key): {e}"))?;
-    Ok(Some(pt))
+    set_key_to_keyring(app, &key)?;
+    if get_key_from_keyring(app)? != Some(key) {
+        return Err(AppError::new(ErrorCode::Keyring, "keyring verification failed; fallback key file was kept"));
+    }
+    save_key_policy(app, KeySource::KeyringOnly)?;
+    let path = fallback_key_path(app)?;
+    if path.exists() {
+        fs::remove_file(&path).context("fallback key delete error")?;
+    }
+    Ok(())
 }
 
-pub fn save_encrypted(app: &tauri::AppHandle, plaintext: &[u8]) -> Result<(), String> {
-    let dir = app_data_dir(app)?;
-    let path = dir.join(DATA_FILENAME);
-    ensure_parent_dir(&path)?;
+fn wrapped_key_path(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    Ok(app_data_dir(app)?.join(WRAPPED_KEY_FILENAME))
+}
 
-    let key = get_or_create_key(app)?;
-    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("cipher init error: {e}"))?;
+fn check_passphrase(passphrase: &str) -> AppResult<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::invalid(format!("passphrase must be at least {MIN_PASSPHRASE_LEN} characters")));
+    }
+    Ok(())
+}
 
+fn write_wrapped_key(app: &tauri::AppHandle, key: &[u8; 32], passphrase: &str) -> AppResult<()> {
+    let kdf = KdfParams::generate();
+    let kek = kdf.derive_key(passphrase)?;
+    let cipher = Aes256Gcm::new_from_slice(&kek).context("cipher init error")?;
     let mut nonce_bytes = [0u8; 12];
     rand::thread_rng().fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
-
     let ct = cipher
-        .encrypt(nonce, plaintext)
-        .map_err(|e| format!("encrypt error: {e}"))?;
+        .encrypt(Nonce::from_slice(&nonce_bytes), key.as_slice())
+        .context_as(ErrorCode::Crypto, "key wrap error")?;
 
     let engine = base64::engine::general_purpose::STANDARD;
-    let env = Envelope {
-        v: 1,
+    let wrapped = WrappedKey {
+        kdf,
         nonce_b64: engine.encode(nonce_bytes),
         ct_b64: engine.encode(ct),
-        hmac_b64: None, // 로컬 저장에는 HMAC 불필요 (AES-GCM이 이미 인증 제공)
     };
-    let out = serde_json::to_string(&env).map_err(|e| format!("envelope serialize error: {e}"))?;
-    fs::write(&path, out.as_bytes()).map_err(|e| format!("data write error: {e}"))?;
+    let out = serde_json::to_string(&wrapped).context("wrapped key serialize error")?;
+    let path = wrapped_key_path(app)?;
+    ensure_parent_dir(&path)?;
+    write_atomic(&path, out.as_bytes(), false).context("wrapped key write error")
+}
+
+fn unwrap_key(app: &tauri::AppHandle, passphrase: &str) -> AppResult<[u8; 32]> {
+    let path = wrapped_key_path(app)?;
+    if !path.exists() {
+        return Err(AppError::invalid_state("lock is not enabled"));
+    }
+    let raw = fs::read_to_string(&path).context("wrapped key read error")?;
+    let wrapped: WrappedKey = serde_json::from_str(&raw).context("wrapped key parse error")?;
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let nonce_bytes = engine
+        .decode(wrapped.nonce_b64.as_bytes())
+        .context("nonce decode error")?;
+    let ct = engine
+        .decode(wrapped.ct_b64.as_bytes())
+        .context("wrapped key decode error")?;
+    if nonce_bytes.len() != 12 {
+        return Err(AppError::new(ErrorCode::Corrupted, "invalid nonce length"));
+    }
+    let kek = wrapped.kdf.derive_key(passphrase)?;
+    let cipher = Aes256Gcm::new_from_slice(&kek).context("cipher init error")?;
+    // AES-GCM 인증 실패 = 비밀번호가 틀림
+    let pt = cipher
+        .decrypt(Nonce::from_slice(&nonce_bytes), ct.as_ref())
+        .map_err(|_| AppError::new(ErrorCode::WrongPassphrase, "wrong password"))?;
+    if pt.len() != 32 {
+        return Err(AppError::new(ErrorCode::Corrupted, "wrapped key has invalid length"));
+    }
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&pt);
+    Ok(key)
+}
+
+// 키체인에 접근할 수 없으면 남은 키도 읽히지 않으므로 넘어가지만,
+// 접근할 수 있는데 지우지 못해 키가 남아 있으면 에러 (평문 키가 남으면 잠금 모드가 의미 없음)
+fn remove_plaintext_key(app: &tauri::AppHandle) -> AppResult<()> {
+    if let Err(e) = delete_key_from_keyring(app) {
+        if get_key_from_keyring(app).is_ok_and(|key| key.is_some()) {
+            return Err(AppError::new(ErrorCode::Keyring, "could not remove the plaintext key from the keyring")
+                .with_details(e));
+        }
+    }
+    let path = fallback_key_path(app)?;
+    if path.exists() {
+        fs::remove_file(&path).context("fallback key delete error")?;
+    }
     Ok(())
 }
 
-fn compute_hmac(key: &[u8; 32], data: &[u8]) -> [u8; 32] {
-    use hmac::Mac;
-    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC can take key of any size");
-    mac.update(data);
-    mac.finalize().into_bytes().into()
+pub fn lock_status(app: &tauri::AppHandle) -> AppResult<LockStatus> {
+    let enabled = wrapped_key_path(app)?.exists();
+    Ok(LockStatus {
+        enabled,
+        locked: enabled && UNLOCKED_KEY.lock().unwrap().is_none(),
+    })
 }
 
-pub fn export_backup(app: &tauri::AppHandle, output_path: &Path, plaintext: &[u8]) -> Result<(), String> {
-    ensure_parent_dir(output_path)?;
+pub fn is_locked(app: &tauri::AppHandle) -> bool {
+    lock_status(app).map(|s| s.locked).unwrap_or(false)
+}
 
+pub fn ensure_unlocked(app: &tauri::AppHandle) -> AppResult<()> {
+    if is_locked(app) {
+        return Err(AppError::new(ErrorCode::Locked, LOCKED));
+    }
+    Ok(())
+}
+
+/// 잠금 모드를 켠다. 데이터 키는 그대로 두고 비밀번호로 감싸 저장한 뒤 평문 키를 지우므로
+/// 기존 데이터를 다시 암호화할 필요가 없다. 켠 직후에는 잠금 해제 상태. 평문 키를 지우지 못하면 켜지 않고 에러.
+pub fn enable_lock(app: &tauri::AppHandle, passphrase: &str) -> AppResult<()> {
+    check_passphrase(passphrase)?;
+    if wrapped_key_path(app)?.exists() {
+        return Err(AppError::invalid_state("lock is already enabled"));
+    }
     let key = get_or_create_key(app)?;
-    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("cipher init error: {e}"))?;
+    write_wrapped_key(app, &key, passphrase)?;
+    if let Err(e) = remove_plaintext_key(app) {
+        // 잠금 모드를 켜지 않은 상태로 되돌림 (평문 키를 다시 저장한 뒤 감싼 키를 지우므로 키를 잃지 않음)
+        store_key(app, &key, load_key_policy(app)?)?;
+        fs::remove_file(wrapped_key_path(app)?).context("wrapped key delete error")?;
+        return Err(e);
+    }
+    *UNLOCKED_KEY.lock().unwrap() = Some(key);
+    Ok(())
+}
 
-    let mut nonce_bytes = [0u8; 12];
-    rand::thread_rng().fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
+/// 잠긴 상태에서만 쓸 수 있다 (이미 풀려 있으면 디스크에서 다시 읽어 메모리의 변경을 덮어쓰게 됨).
+pub fn unlock(app: &tauri::AppHandle, passphrase: &str) -> AppResult<()> {
+    let status = lock_status(app)?;
+    if !status.enabled {
+        return Err(AppError::invalid_state("lock is not enabled"));
+    }
+    if !status.locked {
+        return Err(AppError::invalid_state("already unlocked"));
+    }
+    let key = unwrap_key(app, passphrase)?;
+    *UNLOCKED_KEY.lock().unwrap() = Some(key);
+    Ok(())
+}
 
-    let ct = cipher
-        .encrypt(nonce, plaintext)
-        .map_err(|e| format!("encrypt error: {e}"))?;
+/// 메모리의 데이터 키를 지워 다시 잠근다.
+pub fn lock(app: &tauri::AppHandle) -> AppResult<()> {
+    if !wrapped_key_path(app)?.exists() {
+        return Err(AppError::invalid_state("lock is not enabled"));
+    }
+    *UNLOCKED_KEY.lock().unwrap() = None;
+    Ok(())
+}
 
-    let engine = base64::engine::general_purpose::STANDARD;
-    let ct_b64 = engine.encode(&ct);
+/// 비밀번호만 바꾼다 (같은 데이터 키를 새 비밀번호로 다시 감쌈).
+pub fn change_password(app: &tauri::AppHandle, old_passphrase: &str, new_passphrase: &str) -> AppResult<()> {
+    check_passphrase(new_passphrase)?;
+    let key = unwrap_key(app, old_passphrase)?;
+    write_wrapped_key(app, &key, new_passphrase)?;
+    *UNLOCKED_KEY.lock().unwrap() = Some(key);
+    Ok(())
+}
 
-    // HMAC 서명: nonce + ciphertext
-    let mut signed_data = Vec::with_capacity(12 + ct.len());
-    signed_data.extend_from_slice(&nonce_bytes);
-    signed_data.extend_from_slice(&ct);
-    let hmac = compute_hmac(&key, &signed_data);
-    let hmac_b64 = engine.encode(hmac);
+/// 잠금 모드를 끈다. 평문 키를 먼저 저장한 뒤 감싼 키 파일을 지우므로 중간에 실패해도 키를 잃지 않는다.
+pub fn disable_lock(app: &tauri::AppHandle, passphrase: &str) -> AppResult<()> {
+    let key = unwrap_key(app, passphrase)?;
+    store_key(app, &key, load_key_policy(app)?)?;
+    fs::remove_file(wrapped_key_path(app)?).context("wrapped key delete error")?;
+    *UNLOCKED_KEY.lock().unwrap() = None;
+    Ok(())
+}
 
-    let env = Envelope {
-        v: 1,
-        nonce_b64: engine.encode(nonce_bytes),
-        ct_b64,
-        hmac_b64: Some(hmac_b64),
+// 키 식별자: 키의 SHA-256 앞 8바이트 (키를 드러내지 않고 어떤 키인지 구분)
+fn key_id(key: &[u8; 32]) -> String {
+    Sha256::digest(key)[..8].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn load_retired_keys(app: &tauri::AppHandle) -> AppResult<Vec<RetiredKey>> {
+    let path = app_data_dir(app)?.join(RETIRED_KEYS_FILENAME);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw = fs::read_to_string(&path).context("retired keys read error")?;
+    serde_json::from_str(&raw).context("retired keys parse error")
+}
+
+fn record_retired_key(app: &tauri::AppHandle, id: String, now: i64) -> AppResult<()> {
+    let mut retired = load_retired_keys(app)?;
+    retired.retain(|k| k.key_id != id);
+    retired.push(RetiredKey { key_id: id, retired_at: now });
+    let out = serde_json::to_string(&retired).context("retired keys serialize error")?;
+    write_atomic(&app_data_dir(app)?.join(RETIRED_KEYS_FILENAME), out.as_bytes(), false)
+        .context("retired keys write error")
+}
+
+// envelope에 기록된 키가 현재 키와 다르면 복호화 전에 원인을 알려준다 (key_id가 없는 구버전 파일은 그대로 시도)
+fn check_key_id(app: &tauri::AppHandle, expected: Option<&str>, key: &[u8; 32]) -> AppResult<()> {
+    let Some(expected) = expected else {
+        return Ok(());
     };
-    let out = serde_json::to_string_pretty(&env).map_err(|e| format!("envelope serialize error: {e}"))?;
-    fs::write(output_path, out.as_bytes()).map_err(|e| format!("backup write error: {e}"))?;
+    if expected == key_id(key) {
+        return Ok(());
+    }
+    let retired = load_retired_keys(app).unwrap_or_default();
+    match retired.iter().find(|k| k.key_id == expected) {
+        Some(k) => {
+            let at = chrono::DateTime::from_timestamp(k.retired_at, 0)
+                .map(|dt| dt.format("%Y-%m-%d %H:%M UTC").to_string())
+                .unwrap_or_else(|| k.retired_at.to_string());
+            Err(AppError::new(ErrorCode::WrongKey, format!(
+                "encrypted with retired key {expected} (replaced by key rotation at {at}); it can no longer be decrypted"
+            )))
+        }
+        None => Err(AppError::new(ErrorCode::WrongKey, format!(
+            "encrypted with unknown key {expected} (created on another device or installation)"
+        ))),
+    }
+}
+
+fn rotating_path(path: &Path) -> PathBuf {
+    sibling_path(path, ROTATING_SUFFIX)
+}
+
+// 기기 키로 암호화된 파일 (데이터, 보관 파일, 스냅샷)
+fn key_bound_files(app: &tauri::AppHandle) -> AppResult<Vec<PathBuf>> {
+    let dir = app_data_dir(app)?;
+    let mut files: Vec<PathBuf> = [DATA_FILENAME, ARCHIVE_FILENAME]
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| path.exists())
+        .collect();
+    let snapshots = snapshot_dir(app)?;
+    if snapshots.exists() {
+        for entry in fs::read_dir(&snapshots).context("snapshot dir read error")? {
+            let entry = entry.context("snapshot dir read error")?;
+            if parse_snapshot_name(&entry.file_name().to_string_lossy()).is_some() {
+                files.push(entry.path());
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// 중단된 키 교체를 마무리한다. 새 키가 저장되었으면 준비해 둔 파일로 교체하고,
+/// 저장되기 전에 중단되었으면 준비 파일을 버린다 (원본은 기존 키로 그대로 읽힘).
+pub fn finish_key_rotation(app: &tauri::AppHandle) -> AppResult<()> {
+    let mut staged = Vec::new();
+    for dir in [app_data_dir(app)?, snapshot_dir(app)?] {
+        if !dir.exists() {
+            continue;
+        }
+        for entry in fs::read_dir(&dir).context("data dir read error")? {
+            let path = entry.context("data dir read error")?.path();
+            if path.to_string_lossy().ends_with(ROTATING_SUFFIX) {
+                staged.push(path);
+            }
+        }
+    }
+    if staged.is_empty() {
+        return Ok(());
+    }
+
+    let current = key_id(&get_or_create_key(app)?);
+    for path in staged {
+        let name = path.to_string_lossy().to_string();
+        let target = PathBuf::from(name.trim_end_matches(ROTATING_SUFFIX));
+        let ready = open_sealed(&path).is_ok_and(|sealed| sealed.key_id() == Some(current.as_str()));
+        if ready {
+            fs::rename(&path, &target).context("file replace error")?;
+            // 직전 세대는 교체된 키로 암호화되어 있으므로 정리
+            let prev = previous_path(&target);
+            if prev.exists() {
+                let _ = fs::remove_file(prev);
+            }
+        } else {
+            let _ = fs::remove_file(&path);
+        }
+    }
     Ok(())
 }
 
-pub fn import_backup(app: &tauri::AppHandle, input_path: &Path) -> Result<Vec<u8>, String> {
-    let raw = fs::read_to_string(input_path).map_err(|e| format!("backup read error: {e}"))?;
-    let env: Envelope = serde_json::from_str(&raw).map_err(|e| format!("envelope parse error: {e}"))?;
-    if env.v != 1 {
-        return Err("unsupported backup version".to_string());
+/// 데이터 키를 새로 만들어 기기 키로 암호화된 모든 파일을 다시 암호화하고 새 키 식별자를 반환한다.
+/// 새 키로 암호화한 파일을 먼저 옆에 준비하고 → 새 키를 저장하고 → 원본과 교체하므로
+/// 어느 단계에서 중단되어도 finish_key_rotation이 한쪽으로 정리한다.
+/// 잠금 모드에서는 새 키를 감쌀 비밀번호가 필요하다.
+/// 격리된 파일은 읽을 수 없어 다시 암호화할 수 없으므로, 남아 있으면 교체하지 않는다
+/// (예전 키로 암호화된 채 남으면 다시 시도해도 영영 읽을 수 없게 됨).
+pub fn rotate_key(app: &tauri::AppHandle, passphrase: Option<&str>, now: i64) -> AppResult<String> {
+    if !list_quarantine(app)?.is_empty() {
+        return Err(AppError::invalid_state(
+            "quarantined files exist; retry loading them or remove the quarantine folder before rotating the key",
+        ));
+    }
+    let lock_enabled = wrapped_key_path(app)?.exists();
+    let passphrase = match (lock_enabled, passphrase) {
+        (true, Some(passphrase)) => {
+            unwrap_key(app, passphrase)?; // 비밀번호 확인
+            Some(passphrase)
+        }
+        (true, None) => return Err(AppError::new(ErrorCode::PassphraseRequired, PASSPHRASE_REQUIRED)),
+        (false, _) => None,
+    };
+    finish_key_rotation(app)?;
+
+    let old_key = get_or_create_key(app)?;
+    let mut new_key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut new_key);
+
+    // 1) 새 키로 다시 암호화한 파일을 준비 (원본은 그대로)
+    // 복호화한 평문을 메모리에 모으지 않고 바로 새 키로 암호화해 흘려 보낸다
+    for path in key_bound_files(app)? {
+        let sealed = open_sealed(&path)?;
+        let compression = sealed.compression()?;
+        write_atomic_with(&rotating_path(&path), false, |file| {
+            write_sealed(app, file, &new_key, None, compression, |out| {
+                read_sealed(app, sealed, None, |pt| io::copy(pt, out).map(|_| ()).context("re-encrypt error"))
+            })
+        })
+        .map_err(|e| AppError { details: Some(path.display().to_string()), ..e })?;
+    }
+
+    // 2) 새 키 저장 (이 시점부터 준비한 파일이 유효)
+    match passphrase {
+        Some(passphrase) => {
+            write_wrapped_key(app, &new_key, passphrase)?;
+            *UNLOCKED_KEY.lock().unwrap() = Some(new_key);
+        }
+        None => store_key(app, &new_key, load_key_policy(app)?)?,
+    }
+    if let Err(e) = record_retired_key(app, key_id(&old_key), now) {
+        eprintln!("retired key record failed: {e}");
     }
 
-    let hmac_b64 = env.hmac_b64.ok_or_else(|| "missing HMAC signature (file may be corrupted)".to_string())?;
+    // 3) 원본과 교체
+    finish_key_rotation(app)?;
+    Ok(key_id(&new_key))
+}
+
+// v3 AAD: 앱 식별자와 헤더 필드를 암호문에 묶어 다른 앱의 파일이나 헤더를 바꾼 파일은 복호화되지 않게 한다
+fn associated_data(app: &tauri::AppHandle, env: &Envelope) -> Vec<u8> {
+    format!(
+        "{}|v{}|{}|{}|{}",
+        service_name(app),
+        env.v,
+        env.cipher.as_deref().unwrap_or_default(),
+        env.key_id.as_deref().unwrap_or_default(),
+        env.compression.as_deref().unwrap_or_default(),
+    )
+    .into_bytes()
+}
+
+fn check_format_version(v: u32) -> AppResult<()> {
+    if v > STREAM_VERSION {
+        return Err(AppError::new(ErrorCode::UnsupportedVersion, format!(
+            "file was written by a newer version of the app (format v{v}); please update the app"
+        )));
+    }
+    Ok(())
+}
+
+// 비밀번호로 보호된 파일이면 비밀번호에서, 아니면 기기 키에서 복호화 키를 얻는다
+fn resolve_key(
+    app: &tauri::AppHandle,
+    kdf: Option<&KdfParams>,
+    expected_key_id: Option<&str>,
+    passphrase: Option<&str>,
+) -> AppResult<[u8; 32]> {
+    match kdf {
+        Some(kdf) => kdf.derive_key(passphrase.ok_or_else(|| AppError::new(ErrorCode::PassphraseRequired, PASSPHRASE_REQUIRED))?),
+        None => {
+            let key = get_or_create_key(app)?;
+            check_key_id(app, expected_key_id, &key)?;
+            Ok(key)
+        }
+    }
+}
+
+/// 모든 버전의 envelope를 연다. 비밀번호로 보호된 envelope인데 passphrase가 없으면 PASSPHRASE_REQUIRED.
+fn open_envelope(app: &tauri::AppHandle, env: Envelope, passphrase: Option<&str>) -> AppResult<Vec<u8>> {
+    check_format_version(env.v)?;
+    if !(1..=ENVELOPE_VERSION).contains(&env.v) {
+        return Err(AppError::new(ErrorCode::UnsupportedVersion, format!("unsupported data version: {}", env.v)));
+    }
+    if env.v == 2 && env.kdf.is_none() {
+        return Err(AppError::new(ErrorCode::Corrupted, "missing kdf parameters (file may be corrupted)"));
+    }
+
+    let key = resolve_key(app, env.kdf.as_ref(), env.key_id.as_deref(), passphrase)?;
 
     let engine = base64::engine::general_purpose::STANDARD;
     let nonce_bytes = engine
         .decode(env.nonce_b64.as_bytes())
-        .map_err(|e| format!("nonce decode error: {e}"))?;
+        .context("nonce decode error")?;
     let ct = engine
         .decode(env.ct_b64.as_bytes())
-        .map_err(|e| format!("ciphertext decode error: {e}"))?;
-    let expected_hmac = engine
-        .decode(hmac_b64.as_bytes())
-        .map_err(|e| format!("HMAC decode error: {e}"))?;
-
+        .context("ciphertext decode error")?;
     if nonce_bytes.len() != 12 {
-        return Err("invalid nonce length".to_string());
+        return Err(AppError::new(ErrorCode::Corrupted, "invalid nonce length"));
     }
-    if expected_hmac.len() != 32 {
-        return Err("invalid HMAC length".to_string());
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    let cipher = Aes256Gcm::new_from_slice(&key).context("cipher init error")?;
+    // 비밀번호로 보호된 파일은 비밀번호가 틀린 경우가 대부분이므로 WrongPassphrase로 알린다
+    let (fail_code, wrong_key) = if env.kdf.is_some() {
+        (ErrorCode::WrongPassphrase, "wrong passphrase, or file may be tampered or corrupted")
+    } else {
+        (ErrorCode::Tampered, "tampered or wrong key")
+    };
+
+    let pt = if env.v < 3 {
+        // 구버전 백업은 HMAC 서명 (nonce + ciphertext)을 먼저 검증
+        match &env.hmac_b64 {
+            Some(hmac_b64) => {
+                let expected = engine
+                    .decode(hmac_b64.as_bytes())
+                    .context("HMAC decode error")?;
+                if expected.len() != 32 {
+                    return Err(AppError::new(ErrorCode::Corrupted, "invalid HMAC length"));
+                }
+                let mut signed_data = Vec::with_capacity(12 + ct.len());
+                signed_data.extend_from_slice(&nonce_bytes);
+                signed_data.extend_from_slice(&ct);
+                if compute_hmac(&key, &signed_data).as_slice() != expected.as_slice() {
+                    return Err(AppError::new(fail_code, format!("HMAC verification failed: {wrong_key}")));
+                }
+            }
+            None if env.v == 2 => {
+                return Err(AppError::new(ErrorCode::Corrupted, "missing HMAC signature (file may be corrupted)"))
+            }
+            None => {}
+        }
+        cipher.decrypt(nonce, ct.as_ref())
+    } else {
+        match env.cipher.as_deref() {
+            Some(CIPHER_AES_256_GCM) => {}
+            Some(other) => {
+                return Err(AppError::new(ErrorCode::UnsupportedVersion, format!("unsupported cipher: {other}")))
+            }
+            None => {
+                return Err(AppError::new(ErrorCode::Corrupted, "missing cipher identifier (file may be corrupted)"))
+            }
+        }
+        let aad = associated_data(app, &env);
+        cipher.decrypt(nonce, Payload { msg: &ct, aad: &aad })
     }
+    .map_err(|e| AppError::new(fail_code, format!("decrypt failed ({wrong_key})")).with_details(e))?;
+    decompress(env.compression.as_deref(), pt)
+}
 
-    // HMAC 검증
+fn stream_aad(app: &tauri::AppHandle, header_json: &[u8]) -> Vec<u8> {
+    let mut aad = format!("{}|", service_name(app)).into_bytes();
+    aad.extend_from_slice(header_json);
+    aad
+}
+
+/// 암호화 파일을 복호화하면서 평문을 parse에 흘려 보낸다. 스트림 컨테이너는 청크마다 인증하고
+/// parse가 끝난 뒤 남은 청크까지 확인하므로 잘린 파일은 에러가 된다.
+/// 비밀번호로 보호된 파일인데 passphrase가 없으면 PASSPHRASE_REQUIRED.
+fn read_sealed<T>(
+    app: &tauri::AppHandle,
+    sealed: Sealed,
+    passphrase: Option<&str>,
+    parse: impl FnOnce(&mut dyn Read) -> AppResult<T>,
+) -> AppResult<T> {
+    let (header, header_json, body) = match sealed {
+        Sealed::Envelope(env) => {
+            let pt = open_envelope(app, env, passphrase)?;
+            return parse(&mut pt.as_slice());
+        }
+        Sealed::Stream {
+            header,
+            header_json,
+            body,
+        } => (header, header_json, body),
+    };
+    check_format_version(header.v)?;
+    if !(FIRST_STREAM_VERSION..=STREAM_VERSION).contains(&header.v) {
+        return Err(AppError::new(ErrorCode::UnsupportedVersion, format!("unsupported data version: {}", header.v)));
+    }
+    if header.cipher != CIPHER_AES_256_GCM_STREAM {
+        return Err(AppError::new(ErrorCode::UnsupportedVersion, format!("unsupported cipher: {}", header.cipher)));
+    }
+
+    let key = resolve_key(app, header.kdf.as_ref(), header.key_id.as_deref(), passphrase)?;
+    let key = if header.v == FIRST_STREAM_VERSION { key } else { subkey(&key, STREAM_KEY_LABEL) };
+    let prefix: [u8; NONCE_PREFIX_LEN] = base64::engine::general_purpose::STANDARD
+        .decode(header.nonce_prefix_b64.as_bytes())
+        .context("nonce decode error")?
+        .try_into()
+        .map_err(|_| AppError::new(ErrorCode::Corrupted, "invalid nonce length"))?;
+    let mut reader = StreamReader::new(body, &key, prefix, stream_aad(app, &header_json), header.chunk_size)?;
+    let result = match Compression::from_name(header.compression.as_deref())? {
+        Compression::None => parse(&mut BufReader::new(&mut reader)),
+        Compression::Deflate => parse(&mut BufReader::new(DeflateDecoder::new(&mut reader))),
+        Compression::Zstd => {
+            let decoder = zstd::stream::read::Decoder::new(&mut reader).context("decompress error")?;
+            parse(&mut BufReader::new(decoder))
+        }
+    }
+    .and_then(|value| reader.finish().map(|()| value));
+
+    // 비밀번호로 보호된 파일은 비밀번호가 틀린 경우가 대부분이므로 WrongPassphrase로 알린다
+    result.map_err(|e| match (e.code, &header.kdf) {
+        (ErrorCode::Tampered, Some(_)) => {
+            AppError::new(ErrorCode::WrongPassphrase, "wrong passphrase, or file may be tampered or corrupted").with_details(e)
+        }
+        _ => e,
+    })
+}
+
+/// 현재 형식(스트림 컨테이너)으로 file에 쓴다. body가 쓰는 평문은 청크 단위로 바로 암호화되므로
+/// 평문·암호문 전체를 메모리에 모으지 않는다. kdf가 있으면 비밀번호에서 유도한 키이므로 기기 키 식별자를 남기지 않음
+fn write_sealed(
+    app: &tauri::AppHandle,
+    file: File,
+    key: &[u8; 32],
+    kdf: Option<KdfParams>,
+    compression: Compression,
+    body: impl FnOnce(&mut dyn Write) -> AppResult<()>,
+) -> AppResult<File> {
+    let mut prefix = [0u8; NONCE_PREFIX_LEN];
+    rand::thread_rng().fill_bytes(&mut prefix);
+    let header = StreamHeader {
+        v: STREAM_VERSION,
+        cipher: CIPHER_AES_256_GCM_STREAM.to_string(),
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        nonce_prefix_b64: base64::engine::general_purpose::STANDARD.encode(prefix),
+        compression: compression.name().map(str::to_string),
+        key_id: if kdf.is_none() { Some(key_id(key)) } else { None },
+        kdf,
+    };
+    let header_json = serde_json::to_vec(&header).context("stream header serialize error")?;
+
+    let mut out = BufWriter::new(file);
+    out.write_all(STREAM_MAGIC).context("data write error")?;
+    out.write_all(&(header_json.len() as u32).to_le_bytes()).context("data write error")?;
+    out.write_all(&header_json).context("data write error")?;
+
+    let stream_key = subkey(key, STREAM_KEY_LABEL);
+    let mut writer = StreamWriter::new(out, &stream_key, prefix, stream_aad(app, &header_json), DEFAULT_CHUNK_SIZE)?;
+    match compression {
+        Compression::None => body(&mut writer)?,
+        Compression::Deflate => {
+            let mut encoder = DeflateEncoder::new(writer, flate2::Compression::default());
+            body(&mut encoder)?;
+            writer = encoder.finish().context("compress error")?;
+        }
+        Compression::Zstd => {
+            let mut encoder = zstd::stream::write::Encoder::new(writer, ZSTD_LEVEL).context("compress error")?;
+            body(&mut encoder)?;
+            writer = encoder.finish().context("compress error")?;
+        }
+    }
+    writer
+        .finish()?
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .context("data write error")
+}
+
+// 기기 키로 암호화해 원자적으로 저장
+fn save_sealed<T: serde::Serialize>(
+    app: &tauri::AppHandle,
+    path: &Path,
+    value: &T,
+    compression: Compression,
+    keep_previous: bool,
+) -> AppResult<()> {
     let key = get_or_create_key(app)?;
-    let mut signed_data = Vec::with_capacity(12 + ct.len());
-    signed_data.extend_from_slice(&nonce_bytes);
-    signed_data.extend_from_slice(&ct);
-    let computed_hmac = compute_hmac(&key, &signed_data);
-    if computed_hmac.as_slice() != expected_hmac.as_slice() {
-        return Err("HMAC verification failed: file may be tampered or corrupted".to_string());
+    write_atomic_with(path, keep_previous, |file| {
+        write_sealed(app, file, &key, None, compression, |out| {
+            serde_json::to_writer(out, value).context("serialize error")
+        })
+    })
+}
+
+fn load_encrypted_file<T>(
+    app: &tauri::AppHandle,
+    filename: &str,
+    parse: impl Fn(&mut dyn Read) -> AppResult<T>,
+) -> AppResult<Option<T>> {
+    let path = app_data_dir(app)?.join(filename);
+    let prev = previous_path(&path);
+    let load = |path: &Path| open_sealed(path).and_then(|sealed| read_sealed(app, sealed, None, &parse));
+    if !path.exists() {
+        // 교체 도중(현재 → .prev, 임시 → 현재 사이) 중단된 경우
+        if prev.exists() {
+            return load(&prev).map(Some);
+        }
+        return Ok(None);
     }
 
-    // 복호화
-    let nonce = Nonce::from_slice(&nonce_bytes);
-    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("cipher init error: {e}"))?;
-    let pt = cipher
-        .decrypt(nonce, ct.as_ref())
-        .map_err(|e| format!("decrypt failed: {e}"))?;
-    Ok(pt)
+    match load(&path) {
+        Ok(value) => Ok(Some(value)),
+        // 현재 파일이 잘렸거나 손상되었을 때만 직전 세대로 복구.
+        // 키·버전 문제는 .prev도 같은 이유로 실패하거나 더 오래된 데이터를 조용히 보여주므로 그대로 복구 모드로
+        Err(e) if matches!(e.code, ErrorCode::Corrupted | ErrorCode::Tampered) && prev.exists() => {
+            eprintln!("{filename} is unreadable ({e}), loading previous generation");
+            let value = load(&prev)
+                .map_err(|prev_err| AppError::new(e.code, e.message.clone()).with_details(format!("{e}; previous generation also failed: {prev_err}")))?;
+            Ok(Some(value))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// 저장된 데이터를 복호화하면서 parse로 읽는다 (파일이 없으면 None).
+pub fn load_encrypted<T>(
+    app: &tauri::AppHandle,
+    parse: impl Fn(&mut dyn Read) -> AppResult<T>,
+) -> AppResult<Option<T>> {
+    load_encrypted_file(app, DATA_FILENAME, parse)
+}
+
+pub fn save_encrypted<T: serde::Serialize>(
+    app: &tauri::AppHandle,
+    data: &T,
+    compression: Compression,
+) -> AppResult<()> {
+    save_sealed(app, &app_data_dir(app)?.join(DATA_FILENAME), data, compression, true)
+}
+
+fn quarantine_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    Ok(app_data_dir(app)?.join(QUARANTINE_DIR))
+}
+
+fn list_quarantine(app: &tauri::AppHandle) -> AppResult<Vec<String>> {
+    let dir = quarantine_dir(app)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&dir).context("quarantine dir read error")? {
+        let entry = entry.context("quarantine dir read error")?;
+        names.push(entry.file_name().to_string_lossy().to_string());
+    }
+    Ok(names)
+}
+
+/// 읽을 수 없는 데이터 파일과 직전 세대를 quarantine/으로 옮긴다 (이름 뒤에 격리 시각을 붙임).
+/// 이후 저장이 이 파일들을 덮어쓰지 않는다. 옮긴 파일 이름을 반환.
+pub fn quarantine_data_file(app: &tauri::AppHandle, now: i64) -> AppResult<Vec<String>> {
+    let path = app_data_dir(app)?.join(DATA_FILENAME);
+    let dir = quarantine_dir(app)?;
+    let mut moved = Vec::new();
+    for src in [path.clone(), previous_path(&path)] {
+        if !src.exists() {
+            continue;
+        }
+        let name = format!("{}.{now}", src.file_name().unwrap_or_default().to_string_lossy());
+        let dest = dir.join(&name);
+        ensure_parent_dir(&dest)?;
+        fs::rename(&src, &dest).context("quarantine error")?;
+        moved.push(name);
+    }
+    Ok(moved)
+}
+
+/// 격리한 파일을 원래 자리로 되돌린다 (다시 읽기 시도용).
+pub fn restore_quarantined(app: &tauri::AppHandle, names: &[String]) -> AppResult<()> {
+    let data_dir = app_data_dir(app)?;
+    let dir = quarantine_dir(app)?;
+    for name in names {
+        // 격리 시각 접미사를 떼면 원래 이름 (경로 구분자가 들어간 이름은 거부)
+        let original = name
+            .rsplit_once('.')
+            .map(|(original, _)| original)
+            .filter(|original| !name.contains(['/', '\\']) && original.starts_with(DATA_FILENAME))
+            .ok_or_else(|| AppError::invalid(format!("invalid quarantined file name: {name}")))?;
+        fs::rename(dir.join(name), data_dir.join(original)).context("quarantine restore error")?;
+    }
+    Ok(())
+}
+
+/// 보관된 완료 할 일 파일. 오래 쌓이는 데이터이므로 설정과 관계없이 항상 압축 후 암호화한다.
+pub fn load_archive<T>(
+    app: &tauri::AppHandle,
+    parse: impl Fn(&mut dyn Read) -> AppResult<T>,
+) -> AppResult<Option<T>> {
+    load_encrypted_file(app, ARCHIVE_FILENAME, parse)
 }
 
+pub fn save_archive<T: serde::Serialize>(app: &tauri::AppHandle, archive: &T) -> AppResult<()> {
+    save_sealed(app, &app_data_dir(app)?.join(ARCHIVE_FILENAME), archive, Compression::Zstd, true)
+}
+
+fn snapshot_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    Ok(app_data_dir(app)?.join(SNAPSHOT_DIR))
+}
+
+// "snapshot_{kind}_{unix초}.enc.json" 형식만 인정 (경로 조작 방지)
+fn parse_snapshot_name(name: &str) -> Option<(String, i64)> {
+    let stem = name.strip_prefix(SNAPSHOT_PREFIX)?.strip_suffix(SNAPSHOT_SUFFIX)?;
+    let (kind, ts) = stem.split_once('_')?;
+    if !SNAPSHOT_SCHEDULES.iter().any(|(k, _, _)| *k == kind) {
+        return None;
+    }
+    Some((kind.to_string(), ts.parse().ok()?))
+}
+
+fn snapshot_path(app: &tauri::AppHandle, id: &str) -> AppResult<PathBuf> {
+    if parse_snapshot_name(id).is_none() {
+        return Err(AppError::invalid(format!("invalid snapshot id: {id}")));
+    }
+    let path = snapshot_dir(app)?.join(id);
+    if !path.exists() {
+        return Err(AppError::not_found(format!("snapshot not found: {id}")));
+    }
+    Ok(path)
+}
+
+/// 스냅샷 목록 (최신순)
+pub fn list_snapshots(app: &tauri::AppHandle) -> AppResult<Vec<SnapshotInfo>> {
+    ensure_unlocked(app)?;
+    let dir = snapshot_dir(app)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut out = Vec::new();
+    for entry in fs::read_dir(&dir).context("snapshot dir read error")? {
+        let entry = entry.context("snapshot dir read error")?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some((kind, created_at)) = parse_snapshot_name(&name) {
+            let size_bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            out.push(SnapshotInfo {
+                id: name,
+                kind,
+                created_at,
+                size_bytes,
+            });
+        }
+    }
+    out.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    Ok(out)
+}
+
+/// 종류별로 마지막 스냅샷 이후 간격이 지났으면 새 스냅샷을 만들고 오래된 것은 지운다.
+pub fn snapshot_if_due<T: serde::Serialize>(app: &tauri::AppHandle, data: &T, now: i64) -> AppResult<()> {
+    let existing = list_snapshots(app)?;
+    let dir = snapshot_dir(app)?;
+    for (kind, interval, keep) in SNAPSHOT_SCHEDULES {
+        let latest = existing.iter().find(|s| s.kind == kind);
+        if latest.is_some_and(|s| now - s.created_at < interval) {
+            continue;
+        }
+
+        let name = format!("{SNAPSHOT_PREFIX}{kind}_{now}{SNAPSHOT_SUFFIX}");
+        save_sealed(app, &dir.join(name), data, Compression::Zstd, false).context("snapshot write error")?;
+
+        // 방금 만든 것을 포함해 keep개만 남김 (목록은 최신순)
+        for old in existing.iter().filter(|s| s.kind == kind).skip(keep.saturating_sub(1)) {
+            let _ = fs::remove_file(dir.join(&old.id));
+        }
+    }
+    Ok(())
+}
+
+pub fn load_snapshot<T>(
+    app: &tauri::AppHandle,
+    id: &str,
+    parse: impl FnOnce(&mut dyn Read) -> AppResult<T>,
+) -> AppResult<T> {
+    let path = snapshot_path(app, id)?;
+    read_sealed(app, open_sealed(&path)?, None, parse)
+}
+
+pub fn delete_snapshot(app: &tauri::AppHandle, id: &str) -> AppResult<()> {
+    ensure_unlocked(app)?;
+    let path = snapshot_path(app, id)?;
+    fs::remove_file(path).context("snapshot delete error")
+}
+
+// 하나의 키(기기 키·비밀번호 유도 키)에서 용도(label)별로 독립된 하위 키를 만든다
+fn subkey(key: &[u8; 32], label: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    Hkdf::<Sha256>::new(None, key)
+        .expand(label, &mut out)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    out
+}
+
+// 구버전(v1·v2) 파일 검증용. 당시에는 AES-GCM과 같은 키를 썼으므로 그대로 계산해야 읽을 수 있다
+fn compute_hmac(key: &[u8; 32], data: &[u8]) -> [u8; 32] {
+    use hmac::Mac;
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// 백업 파일을 쓴다. passphrase가 있으면 비밀번호에서 유도한 키로 암호화해
+/// 다른 기기에서도 가져올 수 있고, 없으면 이 기기의 키를 쓴다.
+pub fn export_backup<T: serde::Serialize>(
+    app: &tauri::AppHandle,
+    output_path: &Path,
+    data: &T,
+    compression: Compression,
+    passphrase: Option<&str>,
+) -> AppResult<()> {
+    ensure_parent_dir(output_path)?;
+
+    let (key, kdf) = match passphrase {
+        Some(passphrase) => {
+            check_passphrase(passphrase)?;
+            let kdf = KdfParams::generate();
+            (kdf.derive_key(passphrase)?, Some(kdf))
+        }
+        None => (get_or_create_key(app)?, None),
+    };
+    write_atomic_with(output_path, false, |file| {
+        write_sealed(app, file, &key, kdf, compression, |out| {
+            serde_json::to_writer(out, data).context("serialize error")
+        })
+    })
+    .context("backup write error")
+}
+
+/// 백업 파일을 복호화하면서 parse로 읽는다 (모든 envelope 버전 지원). 비밀번호로 보호된 백업인데 passphrase가 없으면
+/// PASSPHRASE_REQUIRED 에러를 돌려주므로 비밀번호를 받아 다시 호출하면 된다.
+pub fn import_backup<T>(
+    app: &tauri::AppHandle,
+    input_path: &Path,
+    passphrase: Option<&str>,
+    parse: impl FnOnce(&mut dyn Read) -> AppResult<T>,
+) -> AppResult<T> {
+    let sealed = open_sealed(input_path).context("backup read error")?;
+    read_sealed(app, sealed, passphrase, parse)
+}
@@ -0,0 +1,201 @@
+use crate::error::{AppError, AppResult, Context, ErrorCode};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use std::io::{self, Read, Write};
+
+// 청크 단위 AEAD (STREAM 구성). 평문을 chunk_size씩 잘라 각각 AES-256-GCM으로 암호화하므로
+// 파일 전체를 메모리에 올리지 않고 읽고 쓸 수 있다.
+// 청크 nonce = 파일별 무작위 접두사(7) + 청크 번호(4, BE) + 마지막 청크 표시(1)
+// → 청크 순서를 바꾸거나 빼거나 파일 끝을 잘라내면 복호화가 실패한다.
+// 각 청크 앞에는 u32 LE 프레임 (하위 31비트 = 암호문 길이, 최상위 비트 = 마지막 청크)
+
+pub const DEFAULT_CHUNK_SIZE: u32 = 64 * 1024;
+// 손상된 헤더로 큰 버퍼를 잡지 않도록 상한
+pub const MAX_CHUNK_SIZE: u32 = 1024 * 1024;
+pub const NONCE_PREFIX_LEN: usize = 7;
+const TAG_LEN: usize = 16;
+const LAST_CHUNK_FLAG: u32 = 1 << 31;
+
+fn chunk_nonce(prefix: &[u8; NONCE_PREFIX_LEN], counter: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+// Read/Write 인터페이스로 전달하기 위해 AppError를 io::Error로 감싼다 (From<io::Error>가 다시 꺼냄)
+fn io_error(e: AppError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// 평문을 받아 청크 단위로 암호화해 inner에 쓴다. 마지막 청크를 쓰려면 반드시 finish를 호출해야 한다.
+pub struct StreamWriter<W: Write> {
+    inner: W,
+    cipher: Aes256Gcm,
+    prefix: [u8; NONCE_PREFIX_LEN],
+    aad: Vec<u8>,
+    chunk_size: usize,
+    counter: u32,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> StreamWriter<W> {
+    pub fn new(
+        inner: W,
+        key: &[u8; 32],
+        prefix: [u8; NONCE_PREFIX_LEN],
+        aad: Vec<u8>,
+        chunk_size: u32,
+    ) -> AppResult<Self> {
+        Ok(Self {
+            inner,
+            cipher: Aes256Gcm::new_from_slice(key).context("cipher init error")?,
+            prefix,
+            aad,
+            chunk_size: chunk_size as usize,
+            counter: 0,
+            buf: Vec::with_capacity(chunk_size as usize),
+        })
+    }
+
+    fn write_chunk(&mut self, len: usize, last: bool) -> AppResult<()> {
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        let ct = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: &self.buf[..len], aad: &self.aad })
+            .context_as(ErrorCode::Crypto, "encrypt error")?;
+        let frame = ct.len() as u32 | if last { LAST_CHUNK_FLAG } else { 0 };
+        self.inner.write_all(&frame.to_le_bytes()).context("data write error")?;
+        self.inner.write_all(&ct).context("data write error")?;
+        self.buf.drain(..len);
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| AppError::new(ErrorCode::Crypto, "too many chunks"))?;
+        Ok(())
+    }
+
+    /// 남은 평문을 마지막 청크로 쓰고 inner를 돌려준다 (평문이 비어 있어도 마지막 청크는 항상 쓴다).
+    pub fn finish(mut self) -> AppResult<W> {
+        self.write_chunk(self.buf.len(), true)?;
+        self.inner.flush().context("data write error")?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for StreamWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        // 뒤에 더 올지 모르므로 chunk_size를 넘었을 때만 가득 찬 청크를 내보냄 (마지막 청크는 finish에서)
+        while self.buf.len() > self.chunk_size {
+            self.write_chunk(self.chunk_size, false).map_err(io_error)?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// inner에서 청크를 하나씩 읽어 인증·복호화한 평문을 돌려준다.
+/// 읽기가 EOF(0)를 돌려주면 마지막 청크까지 인증된 것이다.
+pub struct StreamReader<R: Read> {
+    inner: R,
+    cipher: Aes256Gcm,
+    prefix: [u8; NONCE_PREFIX_LEN],
+    aad: Vec<u8>,
+    chunk_size: usize,
+    counter: u32,
+    buf: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> StreamReader<R> {
+    pub fn new(
+        inner: R,
+        key: &[u8; 32],
+        prefix: [u8; NONCE_PREFIX_LEN],
+        aad: Vec<u8>,
+        chunk_size: u32,
+    ) -> AppResult<Self> {
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(AppError::new(ErrorCode::Corrupted, format!("invalid chunk size: {chunk_size}")));
+        }
+        Ok(Self {
+            inner,
+            cipher: Aes256Gcm::new_from_slice(key).context("cipher init error")?,
+            prefix,
+            aad,
+            chunk_size: chunk_size as usize,
+            counter: 0,
+            buf: Vec::new(),
+            pos: 0,
+            done: false,
+        })
+    }
+
+    fn read_chunk(&mut self) -> AppResult<()> {
+        let mut frame = [0u8; 4];
+        self.inner
+            .read_exact(&mut frame)
+            .context_as(ErrorCode::Corrupted, "truncated file (missing final chunk)")?;
+        let frame = u32::from_le_bytes(frame);
+        let last = frame & LAST_CHUNK_FLAG != 0;
+        let len = (frame & !LAST_CHUNK_FLAG) as usize;
+        if !(TAG_LEN..=self.chunk_size + TAG_LEN).contains(&len) {
+            return Err(AppError::new(ErrorCode::Corrupted, format!("invalid chunk length: {len}")));
+        }
+        let mut ct = vec![0u8; len];
+        self.inner
+            .read_exact(&mut ct)
+            .context_as(ErrorCode::Corrupted, "truncated chunk")?;
+
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        self.buf = self
+            .cipher
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ct, aad: &self.aad })
+            .context_as(ErrorCode::Tampered, "decrypt failed (tampered or wrong key)")?;
+        self.pos = 0;
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or_else(|| AppError::new(ErrorCode::Corrupted, "too many chunks"))?;
+
+        if last {
+            // 마지막 청크 뒤에 덧붙은 데이터는 거부
+            let mut extra = [0u8; 1];
+            if self.inner.read(&mut extra).context("data read error")? != 0 {
+                return Err(AppError::new(ErrorCode::Corrupted, "unexpected data after final chunk"));
+            }
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    /// 남은 청크를 끝까지 읽어 인증한다. 평문을 끝까지 읽지 않는 파서(압축 해제 등) 뒤에 호출해
+    /// 잘리거나 덧붙은 파일을 걸러낸다.
+    pub fn finish(mut self) -> AppResult<()> {
+        while !self.done {
+            self.read_chunk()?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.read_chunk().map_err(io_error)?;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
@@ -1,5 +1,6 @@
 const tauriInvoke = globalThis?.__TAURI__?.tauri?.invoke;
 const tauriDialog = globalThis?.__TAURI__?.dialog;
+const tauriEvent = globalThis?.__TAURI__?.event;
 
 function $(id) {
     const el = document.getElementById(id);
@@ -7,6 +8,11 @@ function $(id) {
     return el;
 }
 
+// 백엔드 에러는 {code, message, details} 객체, 그 외(대화상자 등)는 문자열
+function errorMessage(e) {
+    return e?.message ?? String(e);
+}
+
 function formatClock(date) {
     return new Intl.DateTimeFormat('ko-KR', {
         weekday: 'short',
@@ -394,25 +400,35 @@ async function exportData() {
     }
     try {
         const timestamp = Math.floor(Date.now() / 1000);
-        const defaultName = `todo_backup_${timestamp}.json`;
+        const defaultName = `todo_backup_${timestamp}.todobak`;
         const filePath = await tauriDialog.save({
             defaultPath: defaultName,
-            filters: [{ name: 'Backup', extensions: ['json'] }],
+            filters: [{ name: 'Backup', extensions: ['todobak'] }],
         });
         if (!filePath || (Array.isArray(filePath) && filePath.length === 0)) {
             return; // 사용자가 취소
         }
         // Tauri v1은 배열을 반환할 수 있으므로 첫 번째 요소 사용
         const path = Array.isArray(filePath) ? filePath[0] : filePath;
+        // 비밀번호로 보호하면 다른 기기에서도 가져올 수 있음
+        let passphrase = null;
+        if (window.confirm('다른 기기에서도 가져올 수 있도록 비밀번호로 보호할까요?')) {
+            passphrase = window.prompt('백업 비밀번호 (8자 이상)');
+            if (passphrase === null) return; // 사용자가 취소
+            if (window.prompt('비밀번호 확인') !== passphrase) {
+                window.alert('비밀번호가 일치하지 않습니다.');
+                return;
+            }
+        }
         console.log('Export path:', path);
         console.log('Invoking with:', { file_path: path });
-        const savedPath = await tauriInvoke('export_data', { file_path: path });
+        const savedPath = await tauriInvoke('export_data', { file_path: path, passphrase });
         window.alert(`백업이 저장되었습니다:\n${savedPath}`);
     } catch (e) {
-        if (String(e).includes('cancelled') || String(e).includes('user cancelled')) {
+        if (errorMessage(e).includes('cancelled')) {
             return; // 사용자가 취소
         }
-        window.alert(`백업 내보내기 실패: ${e}`);
+        window.alert(`백업 내보내기 실패: ${errorMessage(e)}`);
         console.error(e);
     }
 }
@@ -427,14 +443,24 @@ async function importData() {
     }
     try {
         const filePath = await tauriDialog.open({
-            filters: [{ name: 'Backup', extensions: ['json'] }],
+            // .json은 이전 버전 앱에서 만든 백업
+            filters: [{ name: 'Backup', extensions: ['todobak', 'json'] }],
         });
         if (!filePath || (Array.isArray(filePath) && filePath.length === 0)) {
             return; // 사용자가 취소
         }
         // Tauri v1은 배열을 반환할 수 있으므로 첫 번째 요소 사용
         const path = Array.isArray(filePath) ? filePath[0] : filePath;
-        const imported = await tauriInvoke('import_data', { file_path: path });
+        let imported;
+        try {
+            imported = await tauriInvoke('import_data', { file_path: path });
+        } catch (e) {
+            // 비밀번호로 보호된 백업이면 비밀번호를 받아 다시 시도
+            if (e?.code !== 'passphrase_required') throw e;
+            const passphrase = window.prompt('백업 비밀번호를 입력하세요');
+            if (passphrase === null) return; // 사용자가 취소
+            imported = await tauriInvoke('import_data', { file_path: path, passphrase });
+        }
         if (imported && imported.tasks) {
             renderTasks(imported.tasks);
             if (imported.stopwatch) {
@@ -459,10 +485,10 @@ async function importData() {
             window.alert('백업이 성공적으로 가져와졌습니다.');
         }
     } catch (e) {
-        if (String(e).includes('cancelled') || String(e).includes('user cancelled')) {
+        if (errorMessage(e).includes('cancelled')) {
             return; // 사용자가 취소
         }
-        window.alert(`백업 가져오기 실패: ${e}`);
+        window.alert(`백업 가져오기 실패: ${errorMessage(e)}`);
         console.error(e);
     }
 }
@@ -772,7 +798,7 @@ async function setupStats() {
             content.querySelector('.stats-charts').hidden = false;
             empty.hidden = true;
         } catch (e) {
-            window.alert(`통계 조회 실패: ${e}`);
+            window.alert(`통계 조회 실패: ${errorMessage(e)}`);
             console.error(e);
         }
     };
@@ -807,10 +833,10 @@ async function setupStats() {
             });
             window.alert(`CSV가 저장되었습니다:\n${savedPath}`);
         } catch (e) {
-            if (String(e).includes('cancelled') || String(e).includes('user cancelled')) {
+            if (errorMessage(e).includes('cancelled')) {
                 return; // 사용자가 취소
             }
-            window.alert(`CSV 내보내기 실패: ${e}`);
+            window.alert(`CSV 내보내기 실패: ${errorMessage(e)}`);
             console.error(e);
         }
     };
@@ -838,7 +864,67 @@ async function setupStats() {
     }
 }
 
+// 잠금 모드면 비밀번호를 받아 잠금 해제한 뒤에 데이터를 불러옴
+async function unlockIfNeeded() {
+    if (typeof tauriInvoke !== 'function') return;
+    const status = await tauriInvoke('get_lock_status');
+    if (!status?.locked) return;
+    for (;;) {
+        const passphrase = window.prompt('잠금 비밀번호를 입력하세요');
+        if (passphrase === null) continue; // 잠금 해제 전에는 진행할 수 없음
+        try {
+            await tauriInvoke('unlock', { passphrase });
+            return;
+        } catch (e) {
+            window.alert(`잠금 해제 실패: ${errorMessage(e)}`);
+        }
+    }
+}
+
+// 저장된 데이터를 읽지 못했으면(복구 모드) 다시 시도·스냅샷 복원·새로 시작 중에서 고르게 함
+async function recoverIfNeeded() {
+    if (typeof tauriInvoke !== 'function') return;
+    let failure = await tauriInvoke('get_recovery_status');
+    while (failure) {
+        try {
+            const reason = `저장된 데이터를 불러오지 못했습니다 (${failure.kind}).\n${failure.message}`;
+            if (window.confirm(`${reason}\n\n다시 시도할까요?`)) {
+                failure = await tauriInvoke('retry_load');
+                continue;
+            }
+            const snapshots = await tauriInvoke('list_snapshots').catch(() => []);
+            const latest = snapshots[0];
+            if (latest && window.confirm(`가장 최근 스냅샷(${new Date(latest.created_at * 1000).toLocaleString()})으로 복원할까요?`)) {
+                await tauriInvoke('recover_from_snapshot', { id: latest.id });
+                return;
+            }
+            if (window.confirm('빈 데이터로 새로 시작할까요? 읽지 못한 파일은 quarantine 폴더에 보관됩니다.')) {
+                await tauriInvoke('start_fresh');
+                return;
+            }
+        } catch (e) {
+            window.alert(`복구 실패: ${errorMessage(e)}`);
+            failure = await tauriInvoke('get_recovery_status');
+        }
+    }
+}
+
+// 백그라운드 저장이 실패하면 알림 (실패가 이어지는 동안에는 한 번만 옴)
+async function watchPersistFailures() {
+    if (!tauriEvent) return;
+    await tauriEvent.listen('persist-failed', (event) => {
+        window.alert(`저장 실패: ${errorMessage(event.payload?.error)}`);
+        console.error(event.payload);
+    });
+}
+
 startClock();
-setupStopwatch().catch((e) => console.error(e));
-initTodos().catch((e) => console.error(e));
-setupStats().catch((e) => console.error(e));
+watchPersistFailures().catch((e) => console.error(e));
+unlockIfNeeded()
+    .then(recoverIfNeeded)
+    .catch((e) => console.error(e))
+    .then(() => {
+        setupStopwatch().catch((e) => console.error(e));
+        initTodos().catch((e) => console.error(e));
+        setupStats().catch((e) => console.error(e));
+    });